@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::hint::black_box;
+use std::io::BufRead;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use advent_of_code_2022::dyn_problem::DynProblem;
+use advent_of_code_2022::inputs::{self, Kind};
+use advent_of_code_2022::params::Overrides;
+use advent_of_code_2022::parse::{self, ParseError};
+use advent_of_code_2022::registry::{self, Day};
+
+use crate::runner;
+pub use crate::runner::Stage;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    pub warmup: usize,
+    pub runs: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            warmup: 3,
+            runs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &mut [Duration]) -> Self {
+        samples.sort();
+
+        let n = samples.len() as f64;
+        let mean = samples.iter().map(Duration::as_secs_f64).sum::<f64>() / n;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let middle = samples.len() / 2;
+        let median = if samples.len().is_multiple_of(2) {
+            (samples[middle - 1] + samples[middle]) / 2
+        } else {
+            samples[middle]
+        };
+
+        Self {
+            min: samples[0],
+            median,
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
+
+/// Statistics for every stage of a single day.
+pub type DayBench = BTreeMap<Stage, Stats>;
+
+fn measure(config: &Config, mut f: impl FnMut() -> Duration) -> Stats {
+    for _ in 0..config.warmup {
+        f();
+    }
+    let mut samples = (0..config.runs.max(1)).map(|_| f()).collect::<Vec<_>>();
+
+    Stats::from_samples(&mut samples)
+}
+
+/// Benchmarks parsing and both parts of a day.
+///
+/// The input file is read once and parsing is measured on the in-memory text;
+/// both parts run against the same parsed input with the puzzle's parameters.
+/// Inputs and results go through [`black_box`] so the optimizer keeps the measured work.
+pub fn bench(
+    problem: &dyn DynProblem,
+    filename: &Path,
+    config: &Config,
+) -> Result<DayBench, ParseError> {
+    let text =
+        std::fs::read_to_string(filename).map_err(|err| ParseError::io(err).with_file(filename))?;
+    let data = problem
+        .parse(&mut text.as_bytes())
+        .map_err(|err| err.with_file(filename))?;
+    let params = problem
+        .params(&Overrides::default())
+        .map_err(ParseError::input)?;
+
+    let mut stats = DayBench::new();
+    stats.insert(
+        Stage::Parse,
+        measure(config, || {
+            let start = Instant::now();
+            black_box(problem.parse(&mut black_box(text.as_bytes())).ok());
+            start.elapsed()
+        }),
+    );
+    stats.insert(
+        Stage::First,
+        measure(config, || {
+            let start = Instant::now();
+            black_box(problem.first_part(black_box(&*data), black_box(&*params)));
+            start.elapsed()
+        }),
+    );
+    stats.insert(
+        Stage::Second,
+        measure(config, || {
+            let start = Instant::now();
+            black_box(problem.second_part(black_box(&*data), black_box(&*params)));
+            start.elapsed()
+        }),
+    );
+
+    Ok(stats)
+}
+
+/// Benchmark results of several days, keyed by day number.
+///
+/// Saved as one `<day> <stage> <min> <median> <mean> <stddev>` line per stage,
+/// with durations in nanoseconds.
+#[derive(Debug, Clone, Default)]
+pub struct Results {
+    pub days: BTreeMap<u8, DayBench>,
+}
+
+impl Results {
+    pub fn parse(reader: impl BufRead) -> Result<Self, ParseError> {
+        let lines = parse::read_lines(reader)?;
+        let mut results = Results::default();
+
+        for line in parse::numbered(&lines) {
+            let tokens = line.text.split_whitespace().collect::<Vec<_>>();
+            let [day, stage, min, median, mean, stddev] = tokens[..] else {
+                return Err(line.error("expected '<day> <stage> <min> <median> <mean> <stddev>'"));
+            };
+            let stage = Stage::from_name(stage)
+                .ok_or_else(|| line.error_at(stage, format!("unknown stage '{}'", stage)))?;
+            let nanos = |token| line.parse::<u64>(token).map(Duration::from_nanos);
+
+            results.days.entry(line.parse(day)?).or_default().insert(
+                stage,
+                Stats {
+                    min: nanos(min)?,
+                    median: nanos(median)?,
+                    mean: nanos(mean)?,
+                    stddev: nanos(stddev)?,
+                },
+            );
+        }
+
+        Ok(results)
+    }
+
+    pub fn read_file(filename: impl AsRef<Path>) -> Result<Self, ParseError> {
+        let filename = filename.as_ref();
+        let file =
+            std::fs::File::open(filename).map_err(|err| ParseError::io(err).with_file(filename))?;
+
+        Self::parse(std::io::BufReader::new(file)).map_err(|err| err.with_file(filename))
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (day, stats) in &self.days {
+            for (stage, stats) in stats {
+                writeln!(
+                    f,
+                    "{} {} {} {} {} {}",
+                    day,
+                    stage.name(),
+                    stats.min.as_nanos(),
+                    stats.median.as_nanos(),
+                    stats.mean.as_nanos(),
+                    stats.stddev.as_nanos()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Benchmarks the given days, reading inputs from `dir`.
+///
+/// Days that fail to parse or panic are returned separately with the reason.
+pub fn run_days(
+    days: impl IntoIterator<Item = &'static Day>,
+    dir: &Path,
+    config: &Config,
+) -> (Results, Vec<(&'static Day, String)>) {
+    runner::with_silent_panics(|| {
+        let mut results = Results::default();
+        let mut failures = vec![];
+
+        for day in days {
+            let input = inputs::resolve(day, dir, Kind::Puzzle);
+            match runner::isolate(|| {
+                let input = input.map_err(|err| ParseError::input(err.to_string()))?;
+                bench(day.problem, &input, config)
+            }) {
+                Ok(stats) => {
+                    results.days.insert(day.number, stats);
+                }
+                Err(reason) => failures.push((day, reason)),
+            }
+        }
+
+        (results, failures)
+    })
+}
+
+/// Formats the statistics and, given a baseline, the change of the median.
+///
+/// Returns the report and whether any stage got slower than the baseline
+/// by more than `threshold` percent.
+pub fn format_report(
+    results: &Results,
+    baseline: Option<&Results>,
+    threshold: f64,
+) -> (String, bool) {
+    let mut report = String::new();
+    let mut regressed = false;
+
+    write!(
+        report,
+        "{:>3}  {:<24} {:<6} {:>12} {:>12} {:>12} {:>12}",
+        "Day", "Name", "Stage", "Min", "Median", "Mean", "Stddev"
+    )
+    .unwrap();
+    if baseline.is_some() {
+        write!(report, " {:>12} {:>9}", "Baseline", "Change").unwrap();
+    }
+    writeln!(report).unwrap();
+
+    for (number, stats) in &results.days {
+        let name = registry::find(&number.to_string()).map_or("?", |day| day.name);
+        for (stage, stats) in stats {
+            write!(
+                report,
+                "{:>3}  {:<24} {:<6} {:>12} {:>12} {:>12} {:>12}",
+                number,
+                name,
+                stage.name(),
+                runner::format_duration(stats.min),
+                runner::format_duration(stats.median),
+                runner::format_duration(stats.mean),
+                runner::format_duration(stats.stddev),
+            )
+            .unwrap();
+
+            let previous = baseline
+                .and_then(|baseline| baseline.days.get(number))
+                .and_then(|stats| stats.get(stage));
+            if let Some(previous) = previous {
+                let change = (stats.median.as_secs_f64() / previous.median.as_secs_f64().max(1e-9)
+                    - 1.0)
+                    * 100.0;
+                write!(
+                    report,
+                    " {:>12} {:>+8.1}%",
+                    runner::format_duration(previous.median),
+                    change
+                )
+                .unwrap();
+                if change > threshold {
+                    regressed = true;
+                    write!(report, "  REGRESSION").unwrap();
+                }
+            } else if baseline.is_some() {
+                write!(report, " {:>12} {:>9}", "-", "-").unwrap();
+            }
+            writeln!(report).unwrap();
+        }
+    }
+
+    (report, regressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(samples: &[u64]) -> Vec<Duration> {
+        samples
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .collect()
+    }
+
+    fn stats(min: u64, median: u64, mean: u64, stddev: u64) -> Stats {
+        Stats {
+            min: Duration::from_nanos(min),
+            median: Duration::from_nanos(median),
+            mean: Duration::from_nanos(mean),
+            stddev: Duration::from_nanos(stddev),
+        }
+    }
+
+    #[test]
+    fn stats_from_samples() {
+        let odd = Stats::from_samples(&mut millis(&[5, 1, 3]));
+        assert_eq!(odd.min, Duration::from_millis(1));
+        assert_eq!(odd.median, Duration::from_millis(3));
+        assert_eq!(odd.mean, Duration::from_millis(3));
+
+        let even = Stats::from_samples(&mut millis(&[4, 2, 8, 6]));
+        assert_eq!(even.median, Duration::from_millis(5));
+        assert_eq!(even.mean, Duration::from_millis(5));
+        // Population deviation of 2, 4, 6, 8 is sqrt(5)
+        let stddev = even.stddev.as_secs_f64() * 1000.0;
+        assert!((stddev - 5f64.sqrt()).abs() < 1e-6, "{}", stddev);
+
+        let constant = Stats::from_samples(&mut millis(&[7, 7]));
+        assert_eq!(constant.stddev, Duration::ZERO);
+    }
+
+    #[test]
+    fn results_round_trip() {
+        let mut results = Results::default();
+        results
+            .days
+            .entry(1)
+            .or_default()
+            .insert(Stage::Parse, stats(1, 2, 3, 4));
+        results
+            .days
+            .entry(14)
+            .or_default()
+            .insert(Stage::Second, stats(5, 6, 7, 8));
+
+        let text = results.to_string();
+        assert_eq!(text, "1 parse 1 2 3 4\n14 part2 5 6 7 8\n");
+        assert_eq!(Results::parse(text.as_bytes()).unwrap().days, results.days);
+
+        let err = Results::parse("1 parse 1 2 3".as_bytes()).unwrap_err();
+        assert_eq!(
+            err.message,
+            "expected '<day> <stage> <min> <median> <mean> <stddev>'"
+        );
+        let err = Results::parse("1 third 1 2 3 4".as_bytes()).unwrap_err();
+        assert_eq!(
+            (err.column, err.message.as_str()),
+            (3, "unknown stage 'third'")
+        );
+    }
+
+    #[test]
+    fn report_regressions() {
+        let results = |median| {
+            let mut results = Results::default();
+            results
+                .days
+                .entry(1)
+                .or_default()
+                .insert(Stage::First, stats(median, median, median, 0));
+            results
+        };
+        let baseline = results(1_000_000);
+
+        let (report, regressed) = format_report(&results(1_050_000), Some(&baseline), 10.0);
+        assert!(!regressed);
+        assert!(report.contains("+5.0%"), "{}", report);
+        assert!(!report.contains("REGRESSION"));
+
+        let (report, regressed) = format_report(&results(1_200_000), Some(&baseline), 10.0);
+        assert!(regressed);
+        assert!(report.contains("+20.0%  REGRESSION"), "{}", report);
+
+        let (report, regressed) = format_report(&results(1_200_000), None, 10.0);
+        assert!(!regressed);
+        assert!(!report.contains("Baseline"));
+        assert!(report.contains("calorie_counting"));
+    }
+}
@@ -1,16 +1,46 @@
+use crate::generate::{Generate, Rng};
+use crate::params::{self, Params};
+use crate::parse::{self, ParseError};
 use crate::problem::Problem;
+use crate::registry::Part;
+use crate::single_pass::SinglePass;
+use std::cmp::Reverse;
 use std::collections::BinaryHeap;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
+
+#[derive(Debug, Clone)]
+pub struct CalorieParams {
+    /// Number of best-stocked elves summed in the second part.
+    pub top: usize,
+}
+
+impl Default for CalorieParams {
+    fn default() -> Self {
+        Self { top: 3 }
+    }
+}
+
+impl Params for CalorieParams {
+    const NAMES: &'static [&'static str] = &["top"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "top" => self.top = params::parse_value(name, value)?,
+            _ => return Err(Self::unknown(name)),
+        }
+
+        Ok(())
+    }
+}
 
 pub struct CalorieCounting;
 
 impl CalorieCounting {
-    fn sum_inventory(inventories: Vec<Vec<u64>>) -> BinaryHeap<u64> {
+    fn sum_inventory(inventories: &[Vec<u64>]) -> BinaryHeap<u64> {
         inventories
-            .into_iter()
+            .iter()
             .fold(vec![], |mut arr, subarray| {
-                arr.push(subarray.into_iter().sum::<u64>());
+                arr.push(subarray.iter().sum::<u64>());
                 arr
             })
             .into_iter()
@@ -22,29 +52,169 @@ impl Problem for CalorieCounting {
     type InputData = Vec<Vec<u64>>;
     type OutputDataFirstPart = u64;
     type OutputDataSecondPart = u64;
+    type Params = CalorieParams;
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut elfs = vec![];
+        parse::for_each_section(reader, |snacks| {
+            elfs.push(
+                snacks
+                    .iter()
+                    .map(|line| line.parse::<u64>(line.text))
+                    .collect::<Result<_, _>>()?,
+            );
+            Ok(())
+        })?;
+
+        Ok(elfs)
+    }
+
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        Some(Self::sum_inventory(input).pop().unwrap_or_default())
+    }
+
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        // Iterating a heap is unordered, only the sorted vector gives the top elves
+        Some(
+            Self::sum_inventory(input)
+                .into_sorted_vec()
+                .into_iter()
+                .rev()
+                .take(params.top)
+                .sum(),
+        )
+    }
+}
+
+impl SinglePass for CalorieCounting {
+    /// Holds the snacks of one elf at a time and a min-heap of the `top` best totals.
+    fn solve_single_pass(
+        reader: impl BufRead,
+        params: &Self::Params,
+        part: Option<Part>,
+    ) -> Result<(Option<u64>, Option<u64>), ParseError> {
+        let mut best = 0;
+        let mut top = BinaryHeap::with_capacity(params.top + 1);
+        let tops = Part::Second.is_selected(part);
+        parse::for_each_section(reader, |snacks| {
+            let total = snacks.iter().try_fold(0, |total, line| {
+                Ok::<_, ParseError>(total + line.parse::<u64>(line.text)?)
+            })?;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .fold(vec![vec![]], |mut elfs, line| {
-                if line.is_empty() {
-                    elfs.push(vec![]);
-                } else {
-                    elfs.last_mut().unwrap().push(line.parse::<u64>().unwrap())
+            best = best.max(total);
+            if tops {
+                top.push(Reverse(total));
+                if top.len() > params.top {
+                    top.pop();
                 }
-                elfs
+            }
+            Ok(())
+        })?;
+
+        Ok((
+            Part::First.is_selected(part).then_some(best),
+            tops.then(|| top.into_iter().map(|Reverse(total)| total).sum()),
+        ))
+    }
+}
+
+impl Generate for CalorieCounting {
+    /// `size` elves carrying one to ten snacks each.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| {
+                let snacks = rng.range(1..=10);
+                (0..snacks)
+                    .map(|_| rng.range(1_000..=60_000).to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
             })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000";
+
+    #[test]
+    fn first_part_example() {
+        let input = CalorieCounting::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            CalorieCounting::first_part(&input, &Default::default()),
+            Some(24000)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = CalorieCounting::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            CalorieCounting::second_part(&input, &Default::default()),
+            Some(45000)
+        );
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        Self::sum_inventory(input).pop().unwrap()
+    #[test]
+    fn single_pass_example() {
+        assert_eq!(
+            CalorieCounting::solve_single_pass_str(EXAMPLE, &Default::default(), None),
+            Ok((Some(24000), Some(45000)))
+        );
+        assert_eq!(
+            CalorieCounting::solve_single_pass_str(
+                EXAMPLE,
+                &CalorieParams { top: 1 },
+                Some(Part::Second)
+            ),
+            Ok((None, Some(24000)))
+        );
+    }
+
+    #[test]
+    fn single_pass_matches_parts() {
+        crate::single_pass::assert_matches_parts::<CalorieCounting>(100);
+    }
+
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        let mut totals = input
+            .split("\n\n")
+            .map(|elf| elf.lines().map(|snack| snack.parse::<u64>().unwrap()).sum())
+            .collect::<Vec<u64>>();
+        let mut top = vec![];
+        while top.len() < 3 && !totals.is_empty() {
+            let max = (0..totals.len()).max_by_key(|&i| totals[i]).unwrap();
+            top.push(totals.remove(max));
+        }
+
+        (top[0], Some(top.iter().sum()))
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        Some(Self::sum_inventory(input).into_iter().take(3).sum())
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<CalorieCounting>(100, reference);
     }
 }
@@ -1,45 +1,84 @@
+use crate::generate::{Generate, Rng};
+use crate::parse::{self, Line, ParseError};
 use crate::problem::Problem;
-use std::io::{BufRead, BufReader};
+use crate::registry::Part;
+use crate::single_pass::SinglePass;
+use std::io::BufRead;
 use std::ops::RangeInclusive;
-use std::path::Path;
 
 pub struct CampCleanup;
 
+impl CampCleanup {
+    fn parse_pair(line: &Line) -> Result<(RangeInclusive<u64>, RangeInclusive<u64>), ParseError> {
+        let [ll, lr, rl, rr] = line.fields(line.text, "{}-{},{}-{}")?;
+
+        Ok((
+            line.parse::<u64>(ll)?..=line.parse::<u64>(lr)?,
+            line.parse::<u64>(rl)?..=line.parse::<u64>(rr)?,
+        ))
+    }
+}
+
 impl Problem for CampCleanup {
     type InputData = Vec<(RangeInclusive<u64>, RangeInclusive<u64>)>;
     type OutputDataFirstPart = u64;
     type OutputDataSecondPart = u64;
+    type Params = ();
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut pairs = vec![];
+        parse::for_each_line(reader, |line| {
+            pairs.push(CampCleanup::parse_pair(&line)?);
+            Ok(())
+        })?;
+
+        Ok(pairs)
+    }
+
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        Some(
+            input
+                .iter()
+                .filter(|(l, r)| l.is_overlap_fully(r) || r.is_overlap_fully(l))
+                .count() as u64,
+        )
+    }
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        let lines = reader.lines().map(Result::unwrap).collect::<Vec<_>>();
-        lines
-            .iter()
-            .map(|line| line.split_once(',').unwrap())
-            .map(|(l, r)| (l.split_once('-').unwrap(), r.split_once('-').unwrap()))
-            .map(|((ll, lr), (rl, rr))| {
-                (
-                    ll.parse::<u64>().unwrap()..=lr.parse::<u64>().unwrap(),
-                    rl.parse::<u64>().unwrap()..=rr.parse::<u64>().unwrap(),
-                )
-            })
-            .collect()
-    }
-
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        input
-            .into_iter()
-            .filter(|(l, r)| l.is_overlap_fully(r) || r.is_overlap_fully(l))
-            .count() as u64
-    }
-
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        Some(input.into_iter().filter(|(l, r)| l.is_overlap(r)).count() as u64)
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        Some(input.iter().filter(|(l, r)| l.is_overlap(r)).count() as u64)
     }
 }
 
-trait OverlapRange<T: Ord + PartialOrd> {
+impl SinglePass for CampCleanup {
+    fn solve_single_pass(
+        reader: impl BufRead,
+        _params: &Self::Params,
+        part: Option<Part>,
+    ) -> Result<(Option<u64>, Option<u64>), ParseError> {
+        let mut contained = Part::First.is_selected(part).then_some(0);
+        let mut overlapping = Part::Second.is_selected(part).then_some(0);
+        parse::for_each_line(reader, |line| {
+            let (l, r) = CampCleanup::parse_pair(&line)?;
+            if let Some(contained) = &mut contained {
+                *contained += (l.is_overlap_fully(&r) || r.is_overlap_fully(&l)) as u64;
+            }
+            if let Some(overlapping) = &mut overlapping {
+                *overlapping += l.is_overlap(&r) as u64;
+            }
+            Ok(())
+        })?;
+
+        Ok((contained, overlapping))
+    }
+}
+
+pub trait OverlapRange<T: Ord + PartialOrd> {
     fn is_overlap_fully(&self, other: &Self) -> bool;
     fn is_overlap(&self, other: &Self) -> bool;
 }
@@ -53,3 +92,86 @@ impl<T: Ord + PartialOrd> OverlapRange<T> for RangeInclusive<T> {
         self.contains(other.start()) || other.contains(self.start())
     }
 }
+
+impl Generate for CampCleanup {
+    /// `size` pairs of section ranges.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut range = || {
+            let start = rng.range(1..=99);
+            format!("{}-{}", start, rng.range(start..=99))
+        };
+
+        (0..size)
+            .map(|_| format!("{},{}", range(), range()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8";
+
+    #[test]
+    fn first_part_example() {
+        let input = CampCleanup::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            CampCleanup::first_part(&input, &Default::default()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = CampCleanup::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            CampCleanup::second_part(&input, &Default::default()),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn single_pass_example() {
+        assert_eq!(
+            CampCleanup::solve_single_pass_str(EXAMPLE, &Default::default(), None),
+            Ok((Some(2), Some(4)))
+        );
+    }
+
+    #[test]
+    fn single_pass_matches_parts() {
+        crate::single_pass::assert_matches_parts::<CampCleanup>(200);
+    }
+
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        input.lines().fold((0, Some(0)), |(first, second), line| {
+            let sections = line
+                .split(['-', ','])
+                .map(|section| section.parse::<u64>().unwrap())
+                .collect::<Vec<_>>();
+            let left = (sections[0]..=sections[1]).collect::<Vec<_>>();
+            let right = (sections[2]..=sections[3]).collect::<Vec<_>>();
+
+            let contained = left.iter().all(|section| right.contains(section))
+                || right.iter().all(|section| left.contains(section));
+            let overlap = left.iter().any(|section| right.contains(section));
+            (
+                first + contained as u64,
+                second.map(|second| second + overlap as u64),
+            )
+        })
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<CampCleanup>(200, reference);
+    }
+}
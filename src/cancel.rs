@@ -0,0 +1,78 @@
+//! Cooperative cancellation of solvers running on a worker thread.
+//!
+//! The runner gives up on a part that takes too long, but a thread cannot be stopped from
+//! the outside. Long loops call [`checkpoint`] so that an abandoned solver stops too
+//! instead of spinning in the background.
+
+use std::cell::RefCell;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Token>> = const { RefCell::new(None) };
+}
+
+/// Shared flag telling the solvers of a thread to stop.
+#[derive(Debug, Clone, Default)]
+pub struct Token(Arc<AtomicBool>);
+
+impl Token {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Makes this the token [`checkpoint`] looks at on the current thread.
+    pub fn install(&self) {
+        CURRENT.with(|current| *current.borrow_mut() = Some(self.clone()));
+    }
+}
+
+/// Payload of the unwinding started by [`checkpoint`].
+#[derive(Debug)]
+pub struct Cancelled;
+
+/// Unwinds out of the solver if the current thread's token got cancelled.
+///
+/// Unwinding bypasses the panic hook, so nothing gets printed; threads without a token are
+/// never cancelled.
+pub fn checkpoint() {
+    let cancelled =
+        CURRENT.with(|current| current.borrow().as_ref().is_some_and(Token::is_cancelled));
+    if cancelled {
+        panic::resume_unwind(Box::new(Cancelled));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn checkpoint_stops_cancelled_thread() {
+        let token = Token::default();
+        checkpoint();
+
+        let worker = {
+            let token = token.clone();
+            thread::spawn(move || {
+                token.install();
+                checkpoint();
+                token.cancel();
+                checkpoint();
+                unreachable!("checkpoint should unwind once cancelled");
+            })
+        };
+
+        let payload = worker.join().unwrap_err();
+        assert!(payload.downcast_ref::<Cancelled>().is_some());
+        assert!(token.is_cancelled());
+        // Other threads are unaffected
+        checkpoint();
+    }
+}
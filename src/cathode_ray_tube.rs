@@ -1,8 +1,10 @@
+use crate::generate::{Generate, Rng};
+use crate::grid::Grid;
+use crate::parse::{self, Line, ParseError};
 use crate::problem::Problem;
-use std::fmt::Debug;
-use std::io::{BufRead, BufReader};
+use std::fmt::Display;
+use std::io::BufRead;
 use std::ops::Add;
-use std::path::Path;
 use std::str::FromStr;
 
 #[derive(Clone, Debug)]
@@ -20,91 +22,392 @@ impl<T: Add> CpuCommand<T> {
     }
 }
 
-impl<T: Add + FromStr, S: AsRef<str>> From<S> for CpuCommand<T>
+impl<T: Add + FromStr> CpuCommand<T>
 where
-    <T as FromStr>::Err: Debug,
+    <T as FromStr>::Err: Display,
 {
-    fn from(value: S) -> Self {
-        let (l, r) = value
-            .as_ref()
-            .split_once(' ')
-            .unwrap_or((value.as_ref(), ""));
+    fn parse(line: &Line) -> Result<Self, ParseError> {
+        let (l, r) = line.text.split_once(' ').unwrap_or((line.text, ""));
         match l {
-            "addx" => Self::Add(r.parse().unwrap()),
-            "noop" => Self::Noop,
-            _ => panic!("Unknown command"),
+            "addx" => Ok(Self::Add(line.parse(r)?)),
+            "noop" => Ok(Self::Noop),
+            _ => Err(line.error_at(l, format!("unknown command '{}'", l))),
         }
     }
 }
 
 pub struct CathodeRayTube;
 
+impl CathodeRayTube {
+    /// Runs the program on the CRT, calling `on_cycle` with the cycle number, the sprite
+    /// position and the screen after every drawn pixel.
+    ///
+    /// Stops once every pixel is drawn, ignoring the rest of a longer program.
+    fn draw(
+        input: &[CpuCommand<i64>],
+        mut on_cycle: impl FnMut(i64, i64, &Grid<char>),
+    ) -> Grid<char> {
+        let mut crt = Grid::new(40, 6, '.');
+        let mut sprite_pos = 1i64;
+        let mut cycles = 0;
+        let pixels = (crt.width() * crt.height()) as i64;
+
+        'program: for command in input {
+            for _ in 0..command.cycles() {
+                if cycles == pixels {
+                    break 'program;
+                }
+                let cursor_x = cycles % 40;
+                let cursor_y = cycles / 40;
+
+                if ((sprite_pos - 1)..=(sprite_pos + 1)).contains(&cursor_x) {
+                    crt[(cursor_x as usize, cursor_y as usize)] = '#';
+                }
+
+                cycles += 1;
+                on_cycle(cycles, sprite_pos, &crt);
+            }
+
+            if let CpuCommand::Add(operand) = command {
+                sprite_pos += operand;
+            };
+        }
+
+        crt
+    }
+}
+
 impl Problem for CathodeRayTube {
     type InputData = Vec<CpuCommand<i64>>;
     type OutputDataFirstPart = i64;
     type OutputDataSecondPart = String;
+    type Params = ();
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let lines = parse::read_lines(reader)?;
 
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .map(|line| line.into())
-            .collect()
+        parse::numbered(&lines)
+            .map(|line| CpuCommand::parse(&line))
+            .collect::<Result<_, _>>()
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
         let mut limits = vec![20, 60, 100, 140, 180, 220];
-        input
-            .into_iter()
-            .fold((0, 1, 0), |(mut signal, mut x, mut cycles), command| {
-                cycles += command.cycles();
-
-                if let Some(first) = limits.first() {
-                    if cycles >= *first {
-                        signal += first * x;
-                        limits.remove(0);
+        Some(
+            input
+                .iter()
+                .fold((0, 1, 0), |(mut signal, mut x, mut cycles), command| {
+                    cycles += command.cycles();
+
+                    if let Some(first) = limits.first() {
+                        if cycles >= *first {
+                            signal += first * x;
+                            limits.remove(0);
+                        }
                     }
-                }
 
-                if let CpuCommand::Add(operand) = command {
-                    x += operand;
-                };
+                    if let CpuCommand::Add(operand) = command {
+                        x += operand;
+                    };
 
-                (signal, x, cycles)
-            })
-            .0
+                    (signal, x, cycles)
+                })
+                .0,
+        )
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        let mut crt = [['.'; 40]; 6];
-        let mut sprite_pos = 1i64;
-        let mut cycles = 0;
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        let crt = CathodeRayTube::draw(input, |_, _, _| {});
 
-        for command in input {
-            for _ in 0..command.cycles() {
-                let cursor_x = cycles % 40;
-                let cursor_y = cycles / 40;
+        Some(crt.render(|&pixel| pixel))
+    }
 
-                if ((sprite_pos - 1)..=(sprite_pos + 1)).contains(&cursor_x) {
-                    crt[cursor_y as usize][cursor_x as usize] = '#';
-                }
+    /// The screen after every cycle, below the sprite's row; pixels yet to be drawn are blank.
+    fn visualize(
+        input: &Self::InputData,
+        _params: &Self::Params,
+        frame: &mut dyn FnMut(String),
+    ) -> bool {
+        CathodeRayTube::draw(input, |cycles, sprite_pos, crt| {
+            let sprite = (0..crt.width() as i64)
+                .map(|x| {
+                    if (x - sprite_pos).abs() <= 1 {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            let screen = crt
+                .rows()
+                .enumerate()
+                .map(|(y, row)| {
+                    row.iter()
+                        .enumerate()
+                        .map(|(x, &pixel)| {
+                            if ((y * crt.width() + x) as i64) < cycles {
+                                pixel
+                            } else {
+                                ' '
+                            }
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            frame(format!(
+                "Cycle {:>3}, X = {}\n{}\n\n{}",
+                cycles, sprite_pos, sprite, screen
+            ));
+        });
+
+        true
+    }
+}
+
+impl Generate for CathodeRayTube {
+    /// A program running for `size` cycles, but at least the 240 cycles the CRT draws.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let total = size.max(240);
+        let mut program = vec![];
+        let mut cycles = 0;
 
+        while cycles < total {
+            if cycles + 2 <= total && rng.chance(1, 2) {
+                program.push(format!("addx {}", rng.range(-15..=15)));
+                cycles += 2;
+            } else {
+                program.push("noop".to_string());
                 cycles += 1;
             }
+        }
 
-            if let CpuCommand::Add(operand) = command {
-                sprite_pos += operand;
-            };
+        program.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+addx 15
+addx -11
+addx 6
+addx -3
+addx 5
+addx -1
+addx -8
+addx 13
+addx 4
+noop
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx -35
+addx 1
+addx 24
+addx -19
+addx 1
+addx 16
+addx -11
+noop
+noop
+addx 21
+addx -15
+noop
+noop
+addx -3
+addx 9
+addx 1
+addx -3
+addx 8
+addx 1
+addx 5
+noop
+noop
+noop
+noop
+noop
+addx -36
+noop
+addx 1
+addx 7
+noop
+noop
+noop
+addx 2
+addx 6
+noop
+noop
+noop
+noop
+noop
+addx 1
+noop
+noop
+addx 7
+addx 1
+noop
+addx -13
+addx 13
+addx 7
+noop
+addx 1
+addx -33
+noop
+noop
+noop
+addx 2
+noop
+noop
+noop
+addx 8
+noop
+addx -1
+addx 2
+addx 1
+noop
+addx 17
+addx -9
+addx 1
+addx 1
+addx -3
+addx 11
+noop
+noop
+addx 1
+noop
+addx 1
+noop
+noop
+addx -13
+addx -19
+addx 1
+addx 3
+addx 26
+addx -30
+addx 12
+addx -1
+addx 3
+addx 1
+noop
+noop
+noop
+addx -9
+addx 18
+addx 1
+addx 2
+noop
+noop
+addx 9
+noop
+noop
+noop
+addx -1
+addx 2
+addx -37
+addx 1
+addx 3
+noop
+addx 15
+addx -21
+addx 22
+addx -6
+addx 1
+noop
+addx 2
+addx 1
+noop
+addx -10
+noop
+noop
+addx 20
+addx 1
+addx 2
+addx 2
+addx -6
+addx -11
+noop
+noop
+noop";
+
+    #[test]
+    fn first_part_example() {
+        let input = CathodeRayTube::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            CathodeRayTube::first_part(&input, &Default::default()),
+            Some(13140)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = CathodeRayTube::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            CathodeRayTube::second_part(&input, &Default::default()).unwrap(),
+            "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."
+        );
+    }
+
+    /// Lists the register value during every cycle first.
+    fn reference(input: &str) -> (i64, Option<String>) {
+        let mut during = vec![];
+        let mut x = 1;
+        for line in input.lines() {
+            during.push(x);
+            if let Some(value) = line.strip_prefix("addx ") {
+                during.push(x);
+                x += value.parse::<i64>().unwrap();
+            }
         }
 
-        Some(
-            crt.into_iter()
-                .map(|chunk| chunk.into_iter().collect::<String>())
-                .collect::<Vec<_>>()
-                .join("\n"),
-        )
+        let strength = [20, 60, 100, 140, 180, 220]
+            .iter()
+            .map(|&cycle| cycle * during[cycle as usize - 1])
+            .sum();
+        let screen = during[..240]
+            .chunks(40)
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .map(|(pixel, x)| {
+                        if (pixel as i64 - x).abs() <= 1 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        (strength, Some(screen))
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<CathodeRayTube>(0, reference);
+        crate::generate::assert_matches_reference::<CathodeRayTube>(1_000, reference);
     }
 }
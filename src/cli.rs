@@ -0,0 +1,556 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use advent_of_code_2022::inputs::{self, Kind};
+use advent_of_code_2022::params::Overrides;
+use advent_of_code_2022::registry::{self, Day, Part, DAYS};
+
+use crate::bench;
+use crate::fetch;
+use crate::output::Format;
+use crate::runner::DEFAULT_TIMEOUT;
+use crate::visualize::Output;
+
+pub const USAGE: &str = "\
+Usage:
+    advent-of-code-2022 run <day> [--part 1|2] [--input <path> | --example] [--format json|csv]
+                            [--param <name>=<value>]...
+                            [--frames <dir> | --play [--delay <ms>]] [--timeout <seconds>]
+                            [--stream]
+    advent-of-code-2022 watch <day> [--input <path> | --example] [--param <name>=<value>]...
+                              [--interval <ms>]
+    advent-of-code-2022 repl <day> [--input <path> | --example] [--param <name>=<value>]...
+    advent-of-code-2022 all [--dir <path>] [--format json|csv] [--timeout <seconds>]
+    advent-of-code-2022 verify [--answers <path>] [--dir <path>] [--update]
+                               [--timeout <seconds>]
+    advent-of-code-2022 bench [<day>...] [--runs <n>] [--warmup <n>] [--dir <path>]
+                              [--save <path>] [--baseline <path>] [--threshold <percent>]
+    advent-of-code-2022 generate [<day>...] [--size <n>] [--seed <n>] [--dir <path>]
+    advent-of-code-2022 fetch [<day>...] [--dir <path>] [--session-file <path>]
+
+<day> is a day number (1-14) or a day name such as regolith_reservoir.
+Without --input the day reads dayNN.txt (or <day name>.txt) from the inputs
+directory, which is $AOC_INPUT_DIR or else `inputs`; --example reads
+dayNN.example.txt instead. `all`, `verify` and `bench` read the same files from
+--dir (default: the inputs directory).
+--param overrides a puzzle constant, e.g. `run monkey_in_the_middle --param
+relief=1`; an unknown name lists the parameters of the day.
+--frames writes the frames of the day's simulation to <dir> as numbered files
+and --play animates them in the terminal, --delay (default: 50) milliseconds
+apart; rope_bridge, cathode_ray_tube, supply_stacks and regolith_reservoir
+have one.
+--stream solves the day while reading its input, without keeping it in memory;
+calorie_counting, rucksack_reorganization and camp_cleanup support it.
+`watch` solves the day again whenever its input file changes, checking every
+--interval (default: 500) milliseconds, and compares with the last answers.
+`repl` parses the day's input once and then reads commands: `1` and `2` run a
+part, `set <name>=<value>` changes a parameter, `show` prints the parsed input
+(the directory tree, the monkeys or the stacks) and `help` lists the others.
+`all` solves every day and prints a timing report; built with `--features count-allocations` it also
+reports the peak and total bytes allocated by every stage.
+--format json|csv prints one record per day and part with the answer and the
+parse/solve times in nanoseconds instead of the human-readable output.
+--timeout limits parsing and each part to the given seconds (default: 60, 0 for
+no limit) in `run`, the timing report and `verify`; a day running late is
+reported as TIMEOUT. `run --stream` has no time limit.
+`verify` compares answers against the manifest (default: answers.txt) and
+exits with a non-zero status on any mismatch; --update records the current
+answers of every day into the manifest instead.
+`bench` runs every stage of the given days (default: all) repeatedly and
+reports min/median/mean/stddev; --save stores the results and --baseline
+flags stages whose median got slower by more than --threshold (default: 10%).
+`generate` writes random inputs for the given days (default: all) into --dir
+(default: generated) so they can be fed to `all` or `bench`; --size scales the
+input (default: 100) and --seed makes it reproducible (default: from the clock).
+`fetch` downloads the puzzle inputs of the given days (default: all) into --dir
+(default: the inputs directory), skipping those already there. The session
+cookie comes from $AOC_SESSION or else --session-file (default: .aoc-session);
+$AOC_BASE_URL replaces https://adventofcode.com, which is reached through curl.";
+
+#[derive(Debug)]
+pub enum Command {
+    Run {
+        day: &'static Day,
+        part: Option<Part>,
+        input: Option<PathBuf>,
+        kind: Kind,
+        format: Option<Format>,
+        params: Overrides,
+        visualize: Option<Output>,
+        timeout: Option<Duration>,
+        stream: bool,
+    },
+    Watch {
+        day: &'static Day,
+        input: Option<PathBuf>,
+        kind: Kind,
+        params: Overrides,
+        interval: Duration,
+    },
+    Repl {
+        day: &'static Day,
+        input: Option<PathBuf>,
+        kind: Kind,
+        params: Overrides,
+    },
+    All {
+        dir: PathBuf,
+        format: Option<Format>,
+        timeout: Option<Duration>,
+    },
+    Verify {
+        answers: PathBuf,
+        dir: PathBuf,
+        update: bool,
+        timeout: Option<Duration>,
+    },
+    Bench {
+        days: Vec<&'static Day>,
+        dir: PathBuf,
+        config: bench::Config,
+        save: Option<PathBuf>,
+        baseline: Option<PathBuf>,
+        threshold: f64,
+    },
+    Generate {
+        days: Vec<&'static Day>,
+        dir: PathBuf,
+        seed: Option<u64>,
+        size: usize,
+    },
+    Fetch {
+        days: Vec<&'static Day>,
+        dir: PathBuf,
+        session_file: PathBuf,
+    },
+    Help,
+}
+
+impl Command {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+
+        match args.next().as_deref() {
+            Some("run") => Self::parse_run(args),
+            Some("watch") => Self::parse_watch(args),
+            Some("repl") => Self::parse_repl(args),
+            Some("all") => Self::parse_all(args),
+            Some("verify") => Self::parse_verify(args),
+            Some("bench") => Self::parse_bench(args),
+            Some("generate") => Self::parse_generate(args),
+            Some("fetch") => Self::parse_fetch(args),
+            Some("help" | "-h" | "--help") => Ok(Command::Help),
+            Some(command) => Err(format!("Unknown command '{}'", command)),
+            None => Err("Missing command".to_string()),
+        }
+    }
+
+    fn parse_run(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let day = args.next().ok_or("Missing day")?;
+        let day = find_day(&day)?;
+        let mut part = None;
+        let mut input = None;
+        let mut kind = Kind::Puzzle;
+        let mut format = None;
+        let mut params = Overrides::default();
+        let mut frames = None;
+        let mut play = false;
+        let mut delay = 50;
+        let mut timeout = None;
+        let mut stream = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--part" => {
+                    part = Some(
+                        args.next()
+                            .and_then(|part| part.parse().ok())
+                            .and_then(Part::from_number)
+                            .ok_or("--part expects 1 or 2")?,
+                    )
+                }
+                "--input" => {
+                    input = Some(args.next().ok_or("--input expects a path")?.into());
+                }
+                "--example" => kind = Kind::Example,
+                "--format" => format = Some(parse_format(args.next())?),
+                "--param" => {
+                    let param = args.next().unwrap_or_default();
+                    let (name, value) = param
+                        .split_once('=')
+                        .ok_or("--param expects <name>=<value>")?;
+                    params.push(name, value);
+                }
+                "--frames" => {
+                    frames = Some(args.next().ok_or("--frames expects a path")?.into());
+                }
+                "--play" => play = true,
+                "--delay" => delay = parse_value(&arg, args.next())?,
+                "--timeout" => timeout = Some(parse_timeout(args.next())?),
+                "--stream" => stream = true,
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        if input.is_some() && kind == Kind::Example {
+            return Err("--input and --example are exclusive".to_string());
+        }
+        day.check_params(&params)
+            .map_err(|err| format!("Day {}: {}", day.number, err))?;
+
+        let visualize = match (frames, play) {
+            (Some(_), true) => return Err("--frames and --play are exclusive".to_string()),
+            (Some(dir), false) => Some(Output::Files(dir)),
+            (None, true) => Some(Output::Play(Duration::from_millis(delay))),
+            (None, false) => None,
+        };
+        if visualize.is_some() && format.is_some() {
+            return Err("--format cannot be combined with --frames or --play".to_string());
+        }
+        if stream {
+            if day.single_pass.is_none() {
+                return Err(format!("Day {} has no single-pass solver", day.number));
+            }
+            if format.is_some() || visualize.is_some() || timeout.is_some() {
+                return Err(
+                    "--stream cannot be combined with --format, --frames, --play or --timeout"
+                        .to_string(),
+                );
+            }
+        }
+
+        Ok(Command::Run {
+            day,
+            part,
+            input,
+            kind,
+            format,
+            params,
+            visualize,
+            timeout: timeout.unwrap_or(Some(DEFAULT_TIMEOUT)),
+            stream,
+        })
+    }
+
+    fn parse_watch(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let day = args.next().ok_or("Missing day")?;
+        let day = find_day(&day)?;
+        let mut input = None;
+        let mut kind = Kind::Puzzle;
+        let mut params = Overrides::default();
+        let mut interval = 500;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" => {
+                    input = Some(args.next().ok_or("--input expects a path")?.into());
+                }
+                "--param" => {
+                    let param = args.next().unwrap_or_default();
+                    let (name, value) = param
+                        .split_once('=')
+                        .ok_or("--param expects <name>=<value>")?;
+                    params.push(name, value);
+                }
+                "--example" => kind = Kind::Example,
+                "--interval" => interval = parse_value(&arg, args.next())?,
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        if input.is_some() && kind == Kind::Example {
+            return Err("--input and --example are exclusive".to_string());
+        }
+        day.check_params(&params)
+            .map_err(|err| format!("Day {}: {}", day.number, err))?;
+
+        Ok(Command::Watch {
+            day,
+            input,
+            kind,
+            params,
+            interval: Duration::from_millis(interval),
+        })
+    }
+
+    fn parse_repl(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let day = args.next().ok_or("Missing day")?;
+        let day = find_day(&day)?;
+        let mut input = None;
+        let mut kind = Kind::Puzzle;
+        let mut params = Overrides::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" => {
+                    input = Some(args.next().ok_or("--input expects a path")?.into());
+                }
+                "--param" => {
+                    let param = args.next().unwrap_or_default();
+                    let (name, value) = param
+                        .split_once('=')
+                        .ok_or("--param expects <name>=<value>")?;
+                    params.set(name, value);
+                }
+                "--example" => kind = Kind::Example,
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        if input.is_some() && kind == Kind::Example {
+            return Err("--input and --example are exclusive".to_string());
+        }
+        day.check_params(&params)
+            .map_err(|err| format!("Day {}: {}", day.number, err))?;
+
+        Ok(Command::Repl {
+            day,
+            input,
+            kind,
+            params,
+        })
+    }
+
+    fn parse_all(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut dir = inputs::dir();
+        let mut format = None;
+        let mut timeout = Some(DEFAULT_TIMEOUT);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dir" => dir = args.next().ok_or("--dir expects a path")?.into(),
+                "--format" => format = Some(parse_format(args.next())?),
+                "--timeout" => timeout = parse_timeout(args.next())?,
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(Command::All {
+            dir,
+            format,
+            timeout,
+        })
+    }
+
+    fn parse_verify(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut answers = PathBuf::from("answers.txt");
+        let mut dir = inputs::dir();
+        let mut update = false;
+        let mut timeout = Some(DEFAULT_TIMEOUT);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--timeout" => timeout = parse_timeout(args.next())?,
+                "--answers" => answers = args.next().ok_or("--answers expects a path")?.into(),
+                "--dir" => dir = args.next().ok_or("--dir expects a path")?.into(),
+                "--update" => update = true,
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(Command::Verify {
+            answers,
+            dir,
+            update,
+            timeout,
+        })
+    }
+
+    fn parse_bench(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut days = vec![];
+        let mut dir = inputs::dir();
+        let mut config = bench::Config::default();
+        let mut save = None;
+        let mut baseline = None;
+        let mut threshold = 10.0;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--runs" => config.runs = parse_value(&arg, args.next())?,
+                "--warmup" => config.warmup = parse_value(&arg, args.next())?,
+                "--dir" => dir = args.next().ok_or("--dir expects a path")?.into(),
+                "--save" => save = Some(args.next().ok_or("--save expects a path")?.into()),
+                "--baseline" => {
+                    baseline = Some(args.next().ok_or("--baseline expects a path")?.into())
+                }
+                "--threshold" => threshold = parse_value(&arg, args.next())?,
+                day if !day.starts_with("--") => days.push(find_day(day)?),
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        if days.is_empty() {
+            days = DAYS.iter().collect();
+        }
+
+        Ok(Command::Bench {
+            days,
+            dir,
+            config,
+            save,
+            baseline,
+            threshold,
+        })
+    }
+
+    fn parse_generate(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut days = vec![];
+        let mut dir = PathBuf::from("generated");
+        let mut seed = None;
+        let mut size = 100;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--size" => size = parse_value(&arg, args.next())?,
+                "--seed" => seed = Some(parse_value(&arg, args.next())?),
+                "--dir" => dir = args.next().ok_or("--dir expects a path")?.into(),
+                day if !day.starts_with("--") => days.push(find_day(day)?),
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        if let Some(day) = days.iter().find(|day| day.generator.is_none()) {
+            return Err(format!("Day {} has no generator", day.number));
+        }
+        if days.is_empty() {
+            days = DAYS.iter().filter(|day| day.generator.is_some()).collect();
+        }
+
+        Ok(Command::Generate {
+            days,
+            dir,
+            seed,
+            size,
+        })
+    }
+
+    fn parse_fetch(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut days = vec![];
+        let mut dir = inputs::dir();
+        let mut session_file = PathBuf::from(fetch::DEFAULT_SESSION_FILE);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dir" => dir = args.next().ok_or("--dir expects a path")?.into(),
+                "--session-file" => {
+                    session_file = args.next().ok_or("--session-file expects a path")?.into()
+                }
+                day if !day.starts_with("--") => days.push(find_day(day)?),
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        if days.is_empty() {
+            days = DAYS.iter().collect();
+        }
+
+        Ok(Command::Fetch {
+            days,
+            dir,
+            session_file,
+        })
+    }
+}
+
+fn find_day(query: &str) -> Result<&'static Day, String> {
+    registry::find(query).ok_or_else(|| format!("Unknown day '{}'", query))
+}
+
+fn parse_value<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{} expects a number", option))
+}
+
+/// Seconds as a time limit, `0` meaning none.
+fn parse_timeout(value: Option<String>) -> Result<Option<Duration>, String> {
+    match value.and_then(|value| value.parse::<f64>().ok()) {
+        Some(0.0) => Ok(None),
+        seconds => seconds
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .map(Some)
+            .ok_or_else(|| "--timeout expects a number of seconds".to_string()),
+    }
+}
+
+fn parse_format(value: Option<String>) -> Result<Format, String> {
+    value.ok_or("--format expects json or csv")?.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, String> {
+        Command::parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn timeouts() {
+        let cases = [
+            (Some("0"), Ok(None)),
+            (Some("2"), Ok(Some(Duration::from_secs(2)))),
+            (Some("0.25"), Ok(Some(Duration::from_millis(250)))),
+            (Some("-1"), Err(())),
+            (Some("inf"), Err(())),
+            (Some("NaN"), Err(())),
+            (Some("1e300"), Err(())),
+            (Some("soon"), Err(())),
+            (None, Err(())),
+        ];
+
+        for (value, expected) in cases {
+            let timeout = parse_timeout(value.map(str::to_string));
+            assert_eq!(timeout.map_err(|_| ()), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn conflicting_flags() {
+        let cases = [
+            (
+                "run 1 --timeout 1e300",
+                "--timeout expects a number of seconds",
+            ),
+            (
+                "run 1 --input day01.txt --example",
+                "--input and --example are exclusive",
+            ),
+            (
+                "run 1 --frames frames --play",
+                "--frames and --play are exclusive",
+            ),
+            (
+                "run 1 --format json --play",
+                "--format cannot be combined with --frames or --play",
+            ),
+            ("run 8 --stream", "Day 8 has no single-pass solver"),
+            (
+                "run 1 --stream --timeout 5",
+                "--stream cannot be combined with --format, --frames, --play or --timeout",
+            ),
+            (
+                "run 1 --stream --format csv",
+                "--stream cannot be combined with --format, --frames, --play or --timeout",
+            ),
+            (
+                "watch 1 --input day01.txt --example",
+                "--input and --example are exclusive",
+            ),
+            (
+                "repl 1 --input day01.txt --example",
+                "--input and --example are exclusive",
+            ),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(parse(args).unwrap_err(), expected, "{}", args);
+        }
+    }
+
+    #[test]
+    fn run_timeout() {
+        let timeout = |args| match parse(args).unwrap() {
+            Command::Run { timeout, .. } => timeout,
+            command => panic!("{:?}", command),
+        };
+
+        assert_eq!(timeout("run 1"), Some(DEFAULT_TIMEOUT));
+        assert_eq!(timeout("run 1 --timeout 0"), None);
+        assert_eq!(timeout("run 1 --timeout 3"), Some(Duration::from_secs(3)));
+    }
+}
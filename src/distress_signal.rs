@@ -1,7 +1,8 @@
+use crate::generate::{Generate, Rng};
+use crate::parse::{self, ParseError};
 use crate::problem::Problem;
 use std::cmp::Ordering;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -19,19 +20,22 @@ impl Packet {
         }
     }
 
-    fn parse(tokens: &mut Vec<&str>) -> Packet {
+    fn parse(tokens: &mut Vec<&str>) -> Result<Packet, String> {
         let mut inner = vec![];
 
         while let Some(token) = tokens.pop() {
             match token {
-                "[" => inner.push(Self::parse(tokens)),
-                "]" => break,
+                "[" => inner.push(Self::parse(tokens)?),
+                "]" => return Ok(Self::Array(inner)),
                 "," => (/*skip*/),
-                x => inner.push(Packet::Single(x.parse().unwrap())),
+                x => inner.push(Packet::Single(
+                    x.parse()
+                        .map_err(|_| format!("Unexpected token '{}' in packet", x))?,
+                )),
             }
         }
 
-        Self::Array(inner)
+        Err("Packet is missing closing ']'".to_string())
     }
 }
 
@@ -48,7 +52,12 @@ impl FromStr for Packet {
             return Err("Packet should start with '['".to_string());
         }
 
-        Ok(Packet::parse(&mut tokens))
+        let packet = Packet::parse(&mut tokens)?;
+        if let Some(token) = tokens.pop() {
+            return Err(format!("Unexpected token '{}' after packet", token));
+        }
+
+        Ok(packet)
     }
 }
 
@@ -81,8 +90,8 @@ impl Ord for Packet {
 
 #[derive(Debug, Clone)]
 pub struct Pair {
-    left: Packet,
-    right: Packet,
+    pub left: Packet,
+    pub right: Packet,
 }
 
 pub struct DistressSignal;
@@ -91,52 +100,208 @@ impl Problem for DistressSignal {
     type InputData = Vec<Pair>;
     type OutputDataFirstPart = usize;
     type OutputDataSecondPart = usize;
+    type Params = ();
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .collect::<Vec<_>>()
-            .chunks(3)
-            .into_iter()
-            .map(|chunk| {
-                let left = &chunk[0];
-                let right = &chunk[1];
-                let left = Packet::from_str(left).unwrap();
-                let right = Packet::from_str(right).unwrap();
-
-                Pair { left, right }
-            })
-            .collect()
-    }
-
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        input
-            .into_iter()
-            .enumerate()
-            .filter(|(_, el)| el.left.cmp(&el.right) == Ordering::Less)
-            .map(|(i, _)| i + 1)
-            .sum()
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut pairs = vec![];
+        parse::for_each_section(reader, |section| {
+            let [left, right] = section else {
+                return Err(section[0].error("expected a pair of packets"));
+            };
+
+            pairs.push(Pair {
+                left: Packet::from_str(left.text).map_err(|err| left.error(err))?,
+                right: Packet::from_str(right.text).map_err(|err| right.error(err))?,
+            });
+            Ok(())
+        })?;
+
+        Ok(pairs)
+    }
+
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        Some(
+            input
+                .iter()
+                .enumerate()
+                .filter(|(_, el)| el.left.cmp(&el.right) == Ordering::Less)
+                .map(|(i, _)| i + 1)
+                .sum(),
+        )
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
         let two = Packet::Array(vec![Packet::Array(vec![Packet::Single(2)])]);
         let six = Packet::Array(vec![Packet::Array(vec![Packet::Single(6)])]);
 
         let mut input = input
-            .into_iter()
-            .flat_map(|pair| [pair.left, pair.right])
+            .iter()
+            .flat_map(|pair| [&pair.left, &pair.right])
             .collect::<Vec<_>>();
-        input.push(two.clone());
-        input.push(six.clone());
+        input.push(&two);
+        input.push(&six);
 
         input.sort();
 
-        let (Ok(two) | Err(two)) = input.binary_search(&two);
-        let (Ok(six) | Err(six)) = input.binary_search(&six);
+        // Packets such as `[2]` compare equal to a divider, so count the smaller ones
+        // instead of searching for the divider among its equals
+        let two = input.partition_point(|packet| *packet < &two);
+        let six = input.partition_point(|packet| *packet < &six);
 
         Some((two + 1) * (six + 1))
     }
 }
+
+impl Generate for DistressSignal {
+    /// `size` pairs of packets nested at most four lists deep.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        fn packet(rng: &mut Rng, depth: usize) -> String {
+            let items = (0..rng.below(5))
+                .map(|_| {
+                    if depth < 4 && rng.chance(1, 3) {
+                        packet(rng, depth + 1)
+                    } else {
+                        rng.range(0..=10).to_string()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            format!("[{}]", items.join(","))
+        }
+
+        (0..size.max(1))
+            .map(|_| format!("{}\n{}", packet(rng, 0), packet(rng, 0)))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+    #[test]
+    fn first_part_example() {
+        let input = DistressSignal::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            DistressSignal::first_part(&input, &Default::default()),
+            Some(13)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = DistressSignal::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            DistressSignal::second_part(&input, &Default::default()),
+            Some(140)
+        );
+    }
+
+    #[derive(Debug)]
+    enum Value {
+        Integer(u32),
+        List(Vec<Value>),
+    }
+
+    fn value(bytes: &[u8], at: &mut usize) -> Value {
+        if bytes[*at] != b'[' {
+            let start = *at;
+            while bytes[*at].is_ascii_digit() {
+                *at += 1;
+            }
+            return Value::Integer(
+                std::str::from_utf8(&bytes[start..*at])
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+            );
+        }
+
+        *at += 1;
+        let mut list = vec![];
+        while bytes[*at] != b']' {
+            list.push(value(bytes, at));
+            if bytes[*at] == b',' {
+                *at += 1;
+            }
+        }
+        *at += 1;
+
+        Value::List(list)
+    }
+
+    fn in_order(left: &Value, right: &Value) -> Option<bool> {
+        match (left, right) {
+            (Value::Integer(l), Value::Integer(r)) => (l != r).then_some(l < r),
+            (Value::List(l), Value::List(r)) => l
+                .iter()
+                .zip(r)
+                .find_map(|(l, r)| in_order(l, r))
+                .or((l.len() != r.len()).then_some(l.len() < r.len())),
+            (Value::Integer(l), list) => in_order(&Value::List(vec![Value::Integer(*l)]), list),
+            (list, Value::Integer(r)) => in_order(list, &Value::List(vec![Value::Integer(*r)])),
+        }
+    }
+
+    /// Compares with its own parser and places the dividers by counting smaller packets.
+    fn reference(input: &str) -> (usize, Option<usize>) {
+        let packets = input
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| value(line.as_bytes(), &mut 0))
+            .collect::<Vec<_>>();
+
+        let first = packets
+            .chunks(2)
+            .enumerate()
+            .filter(|(_, pair)| in_order(&pair[0], &pair[1]) == Some(true))
+            .map(|(i, _)| i + 1)
+            .sum();
+        let position = |divider: &str| {
+            let divider = value(divider.as_bytes(), &mut 0);
+            1 + packets
+                .iter()
+                .filter(|packet| in_order(packet, &divider) == Some(true))
+                .count()
+        };
+
+        (first, Some(position("[[2]]") * (position("[[6]]") + 1)))
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<DistressSignal>(50, reference);
+    }
+}
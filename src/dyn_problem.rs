@@ -0,0 +1,190 @@
+use std::any::Any;
+use std::fmt::{self, Display};
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::params::{Overrides, Params};
+use crate::parse::ParseError;
+use crate::problem::Problem;
+
+/// Answer of any day, whatever its output type.
+///
+/// Integers compare equal regardless of their original width as long as they have the same
+/// signedness: `Unsigned(24)` and `Signed(24)` are different answers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Unsigned(u128),
+    Signed(i128),
+    Text(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unsigned(answer) => answer.fmt(f),
+            Answer::Signed(answer) => answer.fmt(f),
+            Answer::Text(answer) => answer.fmt(f),
+        }
+    }
+}
+
+macro_rules! answer_from {
+    ($variant:ident, $wide:ty: $($ty:ty),*) => {
+        $(
+            impl From<$ty> for Answer {
+                fn from(answer: $ty) -> Self {
+                    Answer::$variant(answer as $wide)
+                }
+            }
+        )*
+    };
+}
+
+answer_from!(Unsigned, u128: u8, u16, u32, u64, u128, usize);
+answer_from!(Signed, i128: i8, i16, i32, i64, i128, isize);
+
+impl From<String> for Answer {
+    fn from(answer: String) -> Self {
+        Answer::Text(answer)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(answer: &str) -> Self {
+        Answer::Text(answer.to_string())
+    }
+}
+
+/// Object-safe view of a [`Problem`], so days with different input, output and parameter
+/// types fit into one collection.
+///
+/// Every `Problem` whose answers convert into [`Answer`] implements it;
+/// parsed inputs and parameters travel as `Box<dyn Any>` and must come from the same day.
+pub trait DynProblem: Sync {
+    fn parse(&self, reader: &mut dyn BufRead) -> Result<Box<dyn Any>, ParseError>;
+    /// Parses `filename`, see [`Problem::read_file`].
+    fn read_file(&self, filename: &Path) -> Result<Box<dyn Any>, ParseError>;
+    /// Names of the day's parameters, see [`Params::NAMES`].
+    fn param_names(&self) -> &'static [&'static str];
+    /// Default parameters with `overrides` applied.
+    fn params(&self, overrides: &Overrides) -> Result<Box<dyn Any>, String>;
+    fn first_part(&self, input: &dyn Any, params: &dyn Any) -> Option<Answer>;
+    fn second_part(&self, input: &dyn Any, params: &dyn Any) -> Option<Answer>;
+    fn visualize(&self, input: &dyn Any, params: &dyn Any, frame: &mut dyn FnMut(String)) -> bool;
+    fn describe(&self, input: &dyn Any, params: &dyn Any) -> Option<String>;
+}
+
+impl<T> DynProblem for T
+where
+    T: Problem + Sync,
+    T::InputData: 'static,
+    T::Params: 'static,
+    T::OutputDataFirstPart: Into<Answer>,
+    T::OutputDataSecondPart: Into<Answer>,
+{
+    fn parse(&self, reader: &mut dyn BufRead) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new(T::parse(reader)?))
+    }
+
+    fn read_file(&self, filename: &Path) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new(T::read_file(filename)?))
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        T::Params::NAMES
+    }
+
+    fn params(&self, overrides: &Overrides) -> Result<Box<dyn Any>, String> {
+        Ok(Box::new(overrides.apply::<T::Params>()?))
+    }
+
+    fn first_part(&self, input: &dyn Any, params: &dyn Any) -> Option<Answer> {
+        T::first_part(
+            downcast::<T::InputData>(input),
+            downcast::<T::Params>(params),
+        )
+        .map(Into::into)
+    }
+
+    fn second_part(&self, input: &dyn Any, params: &dyn Any) -> Option<Answer> {
+        T::second_part(
+            downcast::<T::InputData>(input),
+            downcast::<T::Params>(params),
+        )
+        .map(Into::into)
+    }
+
+    fn visualize(&self, input: &dyn Any, params: &dyn Any, frame: &mut dyn FnMut(String)) -> bool {
+        T::visualize(
+            downcast::<T::InputData>(input),
+            downcast::<T::Params>(params),
+            frame,
+        )
+    }
+
+    fn describe(&self, input: &dyn Any, params: &dyn Any) -> Option<String> {
+        T::describe(
+            downcast::<T::InputData>(input),
+            downcast::<T::Params>(params),
+        )
+    }
+}
+
+pub(crate) fn downcast<T: 'static>(value: &dyn Any) -> &T {
+    value.downcast_ref().unwrap_or_else(|| {
+        panic!(
+            "expected a {} from the same day",
+            std::any::type_name::<T>()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::DAYS;
+    use crate::tuning_trouble::TuningTrouble;
+
+    #[test]
+    fn answers_compare_across_widths() {
+        assert_eq!(Answer::from(24u64), Answer::from(24usize));
+        assert_ne!(Answer::from(24u64), Answer::from(24i64));
+        assert_eq!(Answer::from(-3i64).to_string(), "-3");
+        assert_eq!(Answer::from("CMZ"), Answer::Text("CMZ".to_string()));
+    }
+
+    #[test]
+    fn days_in_one_collection() {
+        let days: Vec<&dyn DynProblem> = DAYS.iter().map(|day| day.problem).collect();
+        let tuning = days[5];
+
+        let input = tuning
+            .parse(&mut "mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes())
+            .unwrap();
+        let params = tuning.params(&Overrides::default()).unwrap();
+        assert_eq!(
+            tuning.first_part(&*input, &*params),
+            Some(Answer::from(7usize))
+        );
+        assert_eq!(
+            tuning.second_part(&*input, &*params),
+            Some(Answer::from(19usize))
+        );
+
+        let mut overrides = Overrides::default();
+        overrides.push("packet_marker", "14");
+        let params = tuning.params(&overrides).unwrap();
+        assert_eq!(
+            tuning.first_part(&*input, &*params),
+            Some(Answer::from(19usize))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "from the same day")]
+    fn reject_input_of_another_day() {
+        let input = DAYS[0].problem.parse(&mut "1\n2".as_bytes()).unwrap();
+        let params = TuningTrouble.params(&Overrides::default()).unwrap();
+        TuningTrouble.first_part(&*input, &*params);
+    }
+}
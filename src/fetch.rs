@@ -0,0 +1,473 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use advent_of_code_2022::inputs::{self, Kind};
+use advent_of_code_2022::registry::Day;
+
+/// Environment variable holding the session cookie of the Advent of Code website.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+/// File read for the session cookie when [`SESSION_VAR`] is unset.
+pub const DEFAULT_SESSION_FILE: &str = ".aoc-session";
+/// Environment variable overriding [`DEFAULT_BASE_URL`], e.g. to use a local server.
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+const YEAR: u16 = 2022;
+const USER_AGENT: &str = "advent-of-code-2022 input fetcher";
+/// Default [`Http::timeout`] and [`Curl::timeout`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// No session cookie in the environment variable or the file.
+    NoSession(PathBuf),
+    /// The server answered with something else than `200 OK`.
+    Status(u16, String),
+    Io(io::Error),
+    /// The transport could not talk to the server, e.g. a malformed response.
+    Transport(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::NoSession(file) => write!(
+                f,
+                "no session cookie, set {} or write it to {}",
+                SESSION_VAR,
+                file.display()
+            ),
+            FetchError::Status(status, body) => {
+                write!(f, "server answered {}: {}", status, body.trim())
+            }
+            FetchError::Io(err) => err.fmt(f),
+            FetchError::Transport(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<io::Error> for FetchError {
+    fn from(err: io::Error) -> Self {
+        FetchError::Io(err)
+    }
+}
+
+/// Reads the session cookie from [`SESSION_VAR`], or else from `file`.
+///
+/// A missing file means there is no session; any other error reading it is reported.
+pub fn session(file: &Path) -> Result<String, FetchError> {
+    session_from(env::var(SESSION_VAR).ok(), file)
+}
+
+/// [`session`] with the value of [`SESSION_VAR`] given.
+fn session_from(var: Option<String>, file: &Path) -> Result<String, FetchError> {
+    let session = match var {
+        Some(session) => session,
+        None => match fs::read_to_string(file) {
+            Ok(session) => session,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(FetchError::Io(io::Error::new(
+                    err.kind(),
+                    format!("{}: {}", file.display(), err),
+                )))
+            }
+        },
+    };
+
+    match session.trim() {
+        "" => Err(FetchError::NoSession(file.to_path_buf())),
+        session => Ok(session.to_string()),
+    }
+}
+
+/// [`BASE_URL_VAR`], or else the Advent of Code website.
+pub fn base_url() -> String {
+    env::var(BASE_URL_VAR)
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+}
+
+/// Performs a `GET` request authenticated with the session cookie.
+pub trait Transport {
+    fn get(&self, url: &str, session: &str) -> Result<String, FetchError>;
+}
+
+/// Plain HTTP over a TCP connection, for `http://` URLs only.
+#[derive(Debug, Clone, Copy)]
+pub struct Http {
+    /// Limit for connecting and for every read and write, so a stalled server cannot hang
+    /// the fetch.
+    pub timeout: Duration,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl Http {
+    /// Connects to the first address of `address` that answers in time.
+    fn connect(&self, address: &str) -> io::Result<TcpStream> {
+        let mut last_error = None;
+        for address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no address for {}", address),
+            )
+        }))
+    }
+}
+
+impl Transport for Http {
+    fn get(&self, url: &str, session: &str) -> Result<String, FetchError> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| FetchError::Transport(format!("not an http:// URL: {}", url)))?;
+        let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let address = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+
+        let mut stream = self.connect(&address)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        // HTTP/1.0 keeps the response free of chunked encoding
+        write!(
+            stream,
+            "GET {} HTTP/1.0\r\nHost: {}\r\nCookie: session={}\r\nUser-Agent: {}\r\n\r\n",
+            if path.is_empty() { "/" } else { path },
+            host,
+            session,
+            USER_AGENT
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| FetchError::Transport("truncated HTTP response".to_string()))?;
+        let status = head
+            .lines()
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| FetchError::Transport("malformed HTTP status line".to_string()))?;
+
+        match status {
+            200 => Ok(body.to_string()),
+            _ => Err(FetchError::Status(status, body.to_string())),
+        }
+    }
+}
+
+/// HTTPS through the `curl` command, which has to be installed.
+///
+/// The cookie goes through curl's standard input rather than its command line, where
+/// other users could see it. Curl appends the status code to the body, so that a rejected
+/// request is reported like [`Http`] does.
+#[derive(Debug, Clone)]
+pub struct Curl {
+    /// Program and leading arguments to run curl with, `["curl"]` by default.
+    pub command: Vec<String>,
+    /// Limit for connecting and for the whole transfer.
+    pub timeout: Duration,
+}
+
+impl Default for Curl {
+    fn default() -> Self {
+        Self {
+            command: vec!["curl".to_string()],
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl Transport for Curl {
+    fn get(&self, url: &str, session: &str) -> Result<String, FetchError> {
+        let Some((program, leading)) = self.command.split_first() else {
+            return Err(FetchError::Transport("empty curl command".to_string()));
+        };
+        let timeout = self.timeout.as_secs_f64().to_string();
+        let mut child = Command::new(program)
+            .args(leading)
+            .args(["--silent", "--show-error", "--header", "@-"])
+            .args(["--connect-timeout", &timeout, "--max-time", &timeout])
+            .args(["--write-out", "\n%{http_code}"])
+            .args(["--user-agent", USER_AGENT, url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| FetchError::Transport(format!("cannot run curl: {}", err)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "Cookie: session={}", session)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(FetchError::Transport(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        let response = String::from_utf8(output.stdout)
+            .map_err(|_| FetchError::Transport("input is not UTF-8".to_string()))?;
+        let (body, status) = response
+            .rsplit_once('\n')
+            .and_then(|(body, status)| Some((body, status.parse().ok()?)))
+            .ok_or_else(|| FetchError::Transport("missing HTTP status from curl".to_string()))?;
+
+        match status {
+            200 => Ok(body.to_string()),
+            _ => Err(FetchError::Status(status, body.to_string())),
+        }
+    }
+}
+
+/// [`Http`] or [`Curl`], depending on the URL's scheme.
+pub fn transport_for(url: &str) -> Box<dyn Transport> {
+    if url.starts_with("http://") {
+        Box::new(Http::default())
+    } else {
+        Box::new(Curl::default())
+    }
+}
+
+/// Downloads puzzle inputs into the inputs directory, once.
+pub struct Fetcher {
+    pub base_url: String,
+    pub session: String,
+    pub dir: PathBuf,
+    pub transport: Box<dyn Transport>,
+}
+
+impl Fetcher {
+    /// Path of the day's input and whether it got downloaded just now.
+    ///
+    /// An input already in the directory is never fetched again.
+    pub fn fetch(&self, day: &Day) -> Result<(PathBuf, bool), FetchError> {
+        if let Ok(path) = inputs::resolve(day, &self.dir, Kind::Puzzle) {
+            return Ok((path, false));
+        }
+
+        let url = format!(
+            "{}/{}/day/{}/input",
+            self.base_url.trim_end_matches('/'),
+            YEAR,
+            day.number
+        );
+        let input = self.transport.get(&url, &self.session)?;
+
+        // Written aside first, so an interrupted write never looks like a cached input
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(inputs::file_name(day, Kind::Puzzle));
+        let partial = path.with_extension("part");
+        fs::write(&partial, input)?;
+        fs::rename(&partial, &path)?;
+
+        Ok((path, true))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2022::registry;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    const SESSION: &str = "53616c7465645f5f";
+
+    /// Stand-in for the website: serves `/2022/day/<n>/input` to the right session and
+    /// counts the requests.
+    fn serve() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::Relaxed);
+
+                let head = BufReader::new(&stream)
+                    .lines()
+                    .map_while(Result::ok)
+                    .take_while(|line| !line.is_empty())
+                    .collect::<Vec<_>>();
+                let path = head[0].split(' ').nth(1).unwrap_or_default();
+                let authorized = head.contains(&format!("Cookie: session={}", SESSION));
+                let day = path
+                    .strip_prefix("/2022/day/")
+                    .and_then(|rest| rest.strip_suffix("/input"))
+                    .and_then(|day| day.parse::<u8>().ok());
+
+                let (status, body) = match (authorized, day) {
+                    (false, _) => (
+                        "400 Bad Request",
+                        "Puzzle inputs differ by user.".to_string(),
+                    ),
+                    (true, Some(day @ 1..=25)) => ("200 OK", format!("input of day {}\n", day)),
+                    (true, _) => ("404 Not Found", "404 Not Found".to_string()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nConnection: close\r\n\r\n{}",
+                    status, body
+                )
+                .unwrap();
+            }
+        });
+
+        (base_url, requests)
+    }
+
+    fn fetcher(base_url: &str, session: &str, dir: &Path) -> Fetcher {
+        Fetcher {
+            base_url: base_url.to_string(),
+            session: session.to_string(),
+            dir: dir.to_path_buf(),
+            transport: transport_for(base_url),
+        }
+    }
+
+    #[test]
+    fn fetch_once_then_cache() {
+        let (base_url, requests) = serve();
+        let dir = env::temp_dir().join(format!("aoc-2022-fetch-{}", std::process::id()));
+        let fetcher = fetcher(&base_url, SESSION, &dir);
+        let day = registry::find("7").unwrap();
+
+        let (path, downloaded) = fetcher.fetch(day).unwrap();
+        assert!(downloaded);
+        assert_eq!(path, dir.join("day07.txt"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "input of day 7\n");
+
+        assert_eq!(fetcher.fetch(day).unwrap(), (path, false));
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn report_rejected_session() {
+        let (base_url, _) = serve();
+        let dir = env::temp_dir().join(format!("aoc-2022-fetch-bad-{}", std::process::id()));
+        let fetcher = fetcher(&base_url, "expired", &dir);
+
+        let err = fetcher.fetch(registry::find("1").unwrap()).unwrap_err();
+        assert!(matches!(err, FetchError::Status(400, _)), "{:?}", err);
+        assert!(!dir.join("day01.txt").exists());
+    }
+
+    /// Stand-in for curl answering `400` to the expired session and echoing its arguments
+    /// to any other.
+    #[cfg(unix)]
+    fn fake_curl() -> Curl {
+        let script = "\
+            read header
+            case \"$header\" in
+            *expired*) printf 'bad session\\n400' ;;
+            *) printf '%s\\n200' \"$*\" ;;
+            esac";
+
+        Curl {
+            command: ["sh", "-c", script, "curl"].map(str::to_string).to_vec(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn curl_reports_status_and_times_out() {
+        let curl = fake_curl();
+        let url = "https://example.com/2022/day/1/input";
+
+        let arguments = curl.get(url, SESSION).unwrap();
+        assert!(
+            arguments.contains("--connect-timeout 5 --max-time 5"),
+            "{}",
+            arguments
+        );
+        assert!(arguments.ends_with(url), "{}", arguments);
+        let err = curl.get(url, "expired").unwrap_err();
+        assert!(
+            matches!(&err, FetchError::Status(400, body) if body == "bad session"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn give_up_on_stalled_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/2022/day/1/input", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            // Keeps the connection open without ever answering
+            let _stream = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(5));
+        });
+
+        let http = Http {
+            timeout: Duration::from_millis(50),
+        };
+        let err = http.get(&url, SESSION).unwrap_err();
+        assert!(
+            matches!(&err, FetchError::Io(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn session_from_file() {
+        let file = env::temp_dir().join(format!("aoc-2022-session-{}", std::process::id()));
+
+        assert!(matches!(
+            session_from(None, &file),
+            Err(FetchError::NoSession(_))
+        ));
+        fs::write(&file, format!("{}\n", SESSION)).unwrap();
+        assert_eq!(session_from(None, &file).unwrap(), SESSION);
+        assert_eq!(
+            session_from(Some("cookie".to_string()), &file).unwrap(),
+            "cookie"
+        );
+        fs::remove_file(&file).unwrap();
+        assert!(matches!(
+            session_from(Some(" ".to_string()), &file),
+            Err(FetchError::NoSession(_))
+        ));
+
+        let dir = env::temp_dir();
+        let err = session_from(None, &dir).unwrap_err();
+        assert!(matches!(err, FetchError::Io(_)), "{:?}", err);
+        assert!(err.to_string().starts_with(&dir.display().to_string()));
+    }
+}
@@ -0,0 +1,113 @@
+use std::ops::RangeInclusive;
+
+use crate::problem::Problem;
+
+/// SplitMix64 pseudo-random generator: tiny, seedable and good enough for test inputs.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..n`; `n` must be positive.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "empty range");
+        let span = end.abs_diff(start) + 1;
+        start.wrapping_add_unsigned(self.next_u64() % span)
+    }
+
+    /// `true` with a probability of `numerator / denominator`.
+    pub fn chance(&mut self, numerator: usize, denominator: usize) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Random input generation for a [`Problem`].
+///
+/// Generated inputs follow the puzzle format closely enough for both parts to produce an
+/// answer; what `size` scales is documented on each implementation.
+pub trait Generate: Problem {
+    fn generate(rng: &mut Rng, size: usize) -> String;
+}
+
+/// Object-safe view of a [`Generate`] day, as kept in the registry next to its
+/// [`DynProblem`](crate::dyn_problem::DynProblem).
+pub trait DynGenerate: Sync {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String;
+}
+
+impl<T: Generate + Sync> DynGenerate for T {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        T::generate(rng, size)
+    }
+}
+
+/// Checks a solver against a naive `reference` on inputs generated from several seeds.
+#[cfg(test)]
+pub(crate) fn assert_matches_reference<T: Generate>(
+    size: usize,
+    reference: impl Fn(&str) -> (T::OutputDataFirstPart, Option<T::OutputDataSecondPart>),
+) where
+    T::OutputDataFirstPart: PartialEq + std::fmt::Debug,
+    T::OutputDataSecondPart: PartialEq + std::fmt::Debug,
+{
+    for seed in 0..16 {
+        let input = T::generate(&mut Rng::new(seed), size);
+        let data = T::parse_str(&input).unwrap();
+        let (first, second) = reference(&input);
+
+        assert_eq!(
+            (
+                T::first_part(&data, &Default::default()),
+                T::second_part(&data, &Default::default()),
+            ),
+            (Some(first), second),
+            "seed {}, size {}",
+            seed,
+            size
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_is_deterministic() {
+        let (mut a, mut b, mut c) = (Rng::new(7), Rng::new(7), Rng::new(8));
+        let first = (0..5).map(|_| a.next_u64()).collect::<Vec<_>>();
+
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..5).map(|_| c.next_u64()).collect::<Vec<_>>());
+        assert!((0..1000).all(|_| (-3..=3).contains(&a.range(-3..=3))));
+    }
+}
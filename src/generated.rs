@@ -0,0 +1,50 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use advent_of_code_2022::generate::Rng;
+use advent_of_code_2022::inputs::{self, Kind};
+use advent_of_code_2022::registry::Day;
+
+/// Writes a generated input for every day into `dir`, named like puzzle inputs (`day07.txt`).
+///
+/// Every day gets its own generator seeded with `seed`, so a day's input does not depend
+/// on which other days are generated with it. Days without a generator are skipped.
+pub fn write_days(
+    days: impl IntoIterator<Item = &'static Day>,
+    dir: &Path,
+    seed: u64,
+    size: usize,
+) -> io::Result<Vec<(&'static Day, PathBuf)>> {
+    fs::create_dir_all(dir)?;
+
+    days.into_iter()
+        .filter_map(|day| Some((day, day.generator?)))
+        .map(|(day, generator)| {
+            let path = dir.join(inputs::file_name(day, Kind::Puzzle));
+            fs::write(&path, generator.generate(&mut Rng::new(seed), size))?;
+            Ok((day, path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2022::registry::DAYS;
+
+    #[test]
+    fn generated_inputs_are_solvable() {
+        let dir = std::env::temp_dir().join(format!("aoc-2022-generate-{}", std::process::id()));
+
+        for seed in 0..3 {
+            for (day, path) in write_days(&DAYS, &dir, seed, 20).unwrap() {
+                if let Err(err) = day.solve(&path, &Default::default(), None) {
+                    panic!("day {} with seed {}: {}", day.number, seed, err);
+                }
+            }
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
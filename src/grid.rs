@@ -0,0 +1,235 @@
+use std::io::BufRead;
+use std::ops::{Index, IndexMut};
+
+use crate::parse::{self, ParseError};
+use crate::point::Point;
+
+/// Rectangular grid stored row by row in a single vector.
+///
+/// Positions are [`Point`]s (or `(x, y)` pairs): `x` is the column, `y` the row,
+/// `(0, 0)` is the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Builds a grid from row-major cells; `None` if they do not form full rows.
+    pub fn from_cells(width: usize, cells: Vec<T>) -> Option<Self> {
+        if width == 0 || !cells.len().is_multiple_of(width) {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+
+    /// Parses one cell per character, requiring every line to have the same width.
+    pub fn parse(
+        reader: impl BufRead,
+        mut cell: impl FnMut(Point, char) -> Result<T, String>,
+    ) -> Result<Self, ParseError> {
+        let lines = parse::read_lines(reader)?;
+        let mut width = None;
+        let mut cells = vec![];
+
+        for (y, line) in parse::numbered(&lines).enumerate() {
+            let mut row_width = 0;
+            for (x, (i, c)) in line.text.char_indices().enumerate() {
+                cells.push(
+                    cell(Point::from((x, y)), c)
+                        .map_err(|err| line.error_at(&line.text[i..], err))?,
+                );
+                row_width += 1;
+            }
+            if *width.get_or_insert(row_width) != row_width {
+                return Err(line.error("row width differs from the first row"));
+            }
+        }
+
+        Self::from_cells(width.unwrap_or_default(), cells)
+            .ok_or_else(|| ParseError::input("grid is empty"))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, position: impl Into<Point>) -> bool {
+        self.index_of(position.into()).is_some()
+    }
+
+    pub fn get(&self, position: impl Into<Point>) -> Option<&T> {
+        self.index_of(position.into())
+            .map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, position: impl Into<Point>) -> Option<&mut T> {
+        self.index_of(position.into())
+            .map(|index| &mut self.cells[index])
+    }
+
+    fn index_of(&self, Point { x, y }: Point) -> Option<usize> {
+        let x = usize::try_from(x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(y).ok().filter(|&y| y < self.height)?;
+
+        Some(y * self.width + x)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        assert!(x < self.width, "column {} is out of the grid", x);
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point::from((x, y))))
+    }
+
+    /// Positions of the cells matching `pred`, row by row.
+    pub fn find_all<'a>(
+        &'a self,
+        pred: impl Fn(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = Point> + 'a {
+        self.positions()
+            .filter(move |&position| pred(&self[position]))
+    }
+
+    /// Orthogonal neighbours inside the grid.
+    pub fn neighbours4(&self, position: Point) -> impl Iterator<Item = Point> + '_ {
+        position
+            .neighbours4()
+            .filter(|&neighbour| self.contains(neighbour))
+    }
+
+    /// Orthogonal and diagonal neighbours inside the grid.
+    pub fn neighbours8(&self, position: Point) -> impl Iterator<Item = Point> + '_ {
+        position
+            .neighbours8()
+            .filter(|&neighbour| self.contains(neighbour))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Renders the grid as text, one line per row.
+    pub fn render(&self, cell: impl Fn(&T) -> char) -> String {
+        self.rows()
+            .map(|row| row.iter().map(&cell).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<T, P: Into<Point>> Index<P> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: P) -> &Self::Output {
+        let position = position.into();
+        self.get(position)
+            .unwrap_or_else(|| panic!("position {:?} is out of the grid", position))
+    }
+}
+
+impl<T, P: Into<Point>> IndexMut<P> for Grid<T> {
+    fn index_mut(&mut self, position: P) -> &mut Self::Output {
+        let position = position.into();
+        self.get_mut(position)
+            .unwrap_or_else(|| panic!("position {:?} is out of the grid", position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits() -> Grid<u32> {
+        Grid::parse("123\n456".as_bytes(), |_, c| {
+            c.to_digit(10).ok_or_else(|| "not a digit".to_string())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let grid = digits();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get(Point::new(-1, 0)), None);
+        assert_eq!(grid[Point::new(1, 0)], 2);
+        assert_eq!(
+            grid.render(|&d| char::from_digit(d, 10).unwrap()),
+            "123\n456"
+        );
+    }
+
+    #[test]
+    fn parse_reports_position() {
+        let err = Grid::parse("12\n1x".as_bytes(), |_, c| {
+            c.to_digit(10).ok_or_else(|| "not a digit".to_string())
+        })
+        .unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+
+        let err = Grid::parse("12\n1".as_bytes(), |_, c| Ok::<_, String>(c)).unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn views() {
+        let grid = digits();
+
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(
+            grid.column(2).rev().copied().collect::<Vec<_>>(),
+            vec![6, 3]
+        );
+    }
+
+    #[test]
+    fn neighbours_stay_inside() {
+        let grid = digits();
+
+        let mut corner = grid.neighbours4(Point::ORIGIN).collect::<Vec<_>>();
+        corner.sort();
+        assert_eq!(corner, vec![Point::new(0, 1), Point::new(1, 0)]);
+        assert_eq!(grid.neighbours8(Point::new(1, 0)).count(), 5);
+    }
+}
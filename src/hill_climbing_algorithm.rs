@@ -1,23 +1,17 @@
+use crate::cancel;
+use crate::generate::{Generate, Rng};
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::point::Point;
 use crate::problem::Problem;
 use std::collections::VecDeque;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
 #[derive(Debug, Clone)]
 pub struct Graph {
-    map: Vec<Vec<u64>>,
-    start: (usize, usize),
-    end: (usize, usize),
-}
-
-impl Graph {
-    fn width(&self) -> usize {
-        self.map[0].len()
-    }
-
-    fn height(&self) -> usize {
-        self.map.len()
-    }
+    map: Grid<u64>,
+    start: Point,
+    end: Point,
 }
 
 pub struct HillClimbingAlgorith;
@@ -26,109 +20,83 @@ impl Problem for HillClimbingAlgorith {
     type InputData = Graph;
     type OutputDataFirstPart = u64;
     type OutputDataSecondPart = u64;
+    type Params = ();
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut start = None;
+        let mut end = None;
+        let map = Grid::parse(reader, |position, el| match el {
+            'S' => {
+                start = Some(position);
+                Ok(0)
+            }
+            'E' => {
+                end = Some(position);
+                Ok(('z' as u32 - 'a' as u32) as u64)
+            }
+            'a'..='z' => Ok((el as u32 - 'a' as u32) as u64),
+            _ => Err(format!("unexpected height '{}'", el)),
+        })?;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        reader.lines().map(Result::unwrap).enumerate().fold(
-            Graph {
-                map: vec![],
-                start: (0, 0),
-                end: (0, 0),
-            },
-            |mut graph, (y, line)| {
-                let start = line.find('S');
-                let end = line.find('E');
-
-                if let Some(start) = start {
-                    graph.start = (start, y);
-                }
-
-                if let Some(end) = end {
-                    graph.end = (end, y);
-                }
-
-                let row = line
-                    .chars()
-                    .map(|el| {
-                        if el == 'S' {
-                            0
-                        } else if el == 'E' {
-                            ('z' as u32 - 'a' as u32) as u64
-                        } else {
-                            (el as u32 - 'a' as u32) as u64
-                        }
-                    })
-                    .collect();
-                graph.map.push(row);
+        let (Some(start), Some(end)) = (start, end) else {
+            return Err(ParseError::input("map should contain both 'S' and 'E'"));
+        };
 
-                graph
-            },
-        )
+        Ok(Graph { map, start, end })
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-        let mut step_map: Vec<Vec<Option<i32>>> = (0..input.height())
-            .map(|_| (0..input.width()).map(|_| None).collect())
-            .collect();
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        let mut step_map: Grid<Option<i32>> = input.map.map(|_| None);
         let mut stack = VecDeque::from_iter([(input.end, 0)]);
 
         while let Some((pos, steps)) = stack.pop_front() {
-            let height = input.map[pos.1][pos.0];
-
-            for direction in &directions {
-                let next_pos = (pos.0 as i64 + direction.0, pos.1 as i64 + direction.1);
-                if next_pos.0 < 0
-                    || next_pos.0 >= input.width() as i64
-                    || next_pos.1 < 0
-                    || next_pos.1 >= input.height() as i64
-                    || step_map[next_pos.1 as usize][next_pos.0 as usize].is_some()
-                {
+            cancel::checkpoint();
+            let height = input.map[pos];
+
+            for next_pos in input.map.neighbours4(pos) {
+                if step_map[next_pos].is_some() {
                     continue;
                 }
 
-                let next_height = input.map[next_pos.1 as usize][next_pos.0 as usize];
+                let next_height = input.map[next_pos];
                 if height as i64 - next_height as i64 <= 1 {
-                    stack.push_back(((next_pos.0 as usize, next_pos.1 as usize), steps + 1));
-                    step_map[next_pos.1 as usize][next_pos.0 as usize] = Some(steps + 1);
+                    stack.push_back((next_pos, steps + 1));
+                    step_map[next_pos] = Some(steps + 1);
                 }
             }
         }
 
-        step_map[input.start.1][input.start.0].unwrap() as u64
+        step_map[input.start].map(|steps| steps as u64)
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-        let mut step_map: Vec<Vec<Option<i32>>> = (0..input.height())
-            .map(|_| (0..input.width()).map(|_| None).collect())
-            .collect();
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        let mut step_map: Grid<Option<i32>> = input.map.map(|_| None);
         let mut stack = VecDeque::from_iter([(input.end, 0)]);
         let mut min_steps = None;
 
         while let Some((pos, steps)) = stack.pop_front() {
-            let height = input.map[pos.1][pos.0];
+            cancel::checkpoint();
+            let height = input.map[pos];
 
             if height == 0 && steps < min_steps.unwrap_or(i64::MAX) {
                 min_steps = Some(steps);
             }
 
-            for direction in &directions {
-                let next_pos = (pos.0 as i64 + direction.0, pos.1 as i64 + direction.1);
-                if next_pos.0 < 0
-                    || next_pos.0 >= input.width() as i64
-                    || next_pos.1 < 0
-                    || next_pos.1 >= input.height() as i64
-                    || step_map[next_pos.1 as usize][next_pos.0 as usize].is_some()
-                {
+            for next_pos in input.map.neighbours4(pos) {
+                if step_map[next_pos].is_some() {
                     continue;
                 }
 
-                let next_height = input.map[next_pos.1 as usize][next_pos.0 as usize];
+                let next_height = input.map[next_pos];
                 if height as i64 - next_height as i64 <= 1 {
-                    stack.push_back(((next_pos.0 as usize, next_pos.1 as usize), steps + 1));
-                    step_map[next_pos.1 as usize][next_pos.0 as usize] = Some((steps + 1) as i32);
+                    stack.push_back((next_pos, steps + 1));
+                    step_map[next_pos] = Some((steps + 1) as i32);
                 }
             }
         }
@@ -136,3 +104,132 @@ impl Problem for HillClimbingAlgorith {
         min_steps.map(|el| el as u64)
     }
 }
+
+impl Generate for HillClimbingAlgorith {
+    /// A map `size` squares wide (at least 26) and half as tall.
+    ///
+    /// A winding path from left to right climbs steadily from `S` to `E`, so the summit is
+    /// always reachable; the rest of the map is random.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let (width, height) = (size.max(26), (size / 2).max(5));
+        let mut y = rng.below(height);
+        let mut path = vec![];
+        for x in 0..width {
+            path.push((x, y));
+            let turn = rng.below(height);
+            while y != turn {
+                y = if turn > y { y + 1 } else { y - 1 };
+                path.push((x, y));
+            }
+        }
+
+        let mut map = Grid::new(width, height, 'a');
+        for position in map.positions() {
+            map[position] = (b'a' + rng.below(26) as u8) as char;
+        }
+        for (i, &position) in path.iter().enumerate() {
+            map[position] = (b'a' + (25 * i / (path.len() - 1)) as u8) as char;
+        }
+        map[path[0]] = 'S';
+        map[path[path.len() - 1]] = 'E';
+
+        map.render(|&height| height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi";
+
+    #[test]
+    fn first_part_example() {
+        let input = HillClimbingAlgorith::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            HillClimbingAlgorith::first_part(&input, &Default::default()),
+            Some(31)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = HillClimbingAlgorith::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            HillClimbingAlgorith::second_part(&input, &Default::default()),
+            Some(29)
+        );
+    }
+
+    /// Relaxes distances over the whole map until nothing changes instead of a BFS.
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        let map = input
+            .lines()
+            .map(|line| line.bytes().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let elevation = |square: u8| match square {
+            b'S' => b'a',
+            b'E' => b'z',
+            square => square,
+        };
+        let cells = (0..map.len())
+            .flat_map(|y| (0..map[0].len()).map(move |x| (x, y)))
+            .collect::<Vec<_>>();
+
+        let shortest = |start: &dyn Fn(u8) -> bool| {
+            let mut distance = vec![vec![u64::MAX; map[0].len()]; map.len()];
+            for &(x, y) in &cells {
+                if start(map[y][x]) {
+                    distance[y][x] = 0;
+                }
+            }
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for &(x, y) in &cells {
+                    if distance[y][x] == u64::MAX {
+                        continue;
+                    }
+                    for (nx, ny) in [
+                        (x + 1, y),
+                        (x.wrapping_sub(1), y),
+                        (x, y + 1),
+                        (x, y.wrapping_sub(1)),
+                    ] {
+                        let Some(&next) = map.get(ny).and_then(|row| row.get(nx)) else {
+                            continue;
+                        };
+                        if elevation(next) <= elevation(map[y][x]) + 1
+                            && distance[y][x] + 1 < distance[ny][nx]
+                        {
+                            distance[ny][nx] = distance[y][x] + 1;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+
+            let (x, y) = cells
+                .iter()
+                .copied()
+                .find(|&(x, y)| map[y][x] == b'E')
+                .unwrap();
+            distance[y][x]
+        };
+
+        (
+            shortest(&|square| square == b'S'),
+            Some(shortest(&|square| elevation(square) == b'a')),
+        )
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<HillClimbingAlgorith>(30, reference);
+    }
+}
@@ -0,0 +1,147 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::registry::Day;
+
+/// Environment variable overriding [`DEFAULT_DIR`].
+pub const DIR_VAR: &str = "AOC_INPUT_DIR";
+pub const DEFAULT_DIR: &str = "inputs";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// The personal puzzle input.
+    Puzzle,
+    /// The example from the puzzle text.
+    Example,
+}
+
+/// Directory inputs are looked up in: `$AOC_INPUT_DIR`, or `inputs` when it is unset.
+pub fn dir() -> PathBuf {
+    dir_from(env::var_os(DIR_VAR))
+}
+
+fn dir_from(var: Option<OsString>) -> PathBuf {
+    var.filter(|dir| !dir.is_empty())
+        .map_or_else(|| PathBuf::from(DEFAULT_DIR), PathBuf::from)
+}
+
+/// Conventional file name of an input, e.g. `day07.txt` or `day07.example.txt`.
+pub fn file_name(day: &Day, kind: Kind) -> String {
+    match kind {
+        Kind::Puzzle => format!("day{:02}.txt", day.number),
+        Kind::Example => format!("day{:02}.example.txt", day.number),
+    }
+}
+
+/// Paths tried for an input in `dir`, in order.
+///
+/// Puzzle inputs may also use the day's name, e.g. `no_space_left_on_device.txt`.
+pub fn candidates(day: &Day, dir: &Path, kind: Kind) -> Vec<PathBuf> {
+    let mut candidates = vec![dir.join(file_name(day, kind))];
+    if kind == Kind::Puzzle {
+        candidates.push(dir.join(format!("{}.txt", day.name)));
+    }
+
+    candidates
+}
+
+/// The first existing input of `day` in `dir`.
+pub fn resolve(day: &Day, dir: &Path, kind: Kind) -> Result<PathBuf, NotFound> {
+    let candidates = candidates(day, dir, kind);
+    match candidates.iter().find(|path| path.is_file()) {
+        Some(path) => Ok(path.clone()),
+        None => Err(NotFound {
+            day: day.number,
+            kind,
+            candidates,
+        }),
+    }
+}
+
+/// No input file was found; lists every path that was tried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotFound {
+    pub day: u8,
+    pub kind: Kind,
+    pub candidates: Vec<PathBuf>,
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            Kind::Puzzle => "input",
+            Kind::Example => "example input",
+        };
+        let candidates = self
+            .candidates
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "No {} for day {}, looked for {}",
+            kind,
+            self.day,
+            candidates.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry;
+    use std::fs;
+
+    #[test]
+    fn dir_override() {
+        assert_eq!(dir_from(None), Path::new("inputs"));
+        assert_eq!(dir_from(Some("".into())), Path::new("inputs"));
+        assert_eq!(dir_from(Some("/tmp/aoc".into())), Path::new("/tmp/aoc"));
+    }
+
+    #[test]
+    fn resolve_by_day_number() {
+        let dir = std::env::temp_dir().join(format!("aoc-2022-inputs-{}", std::process::id()));
+        let day = registry::find("7").unwrap();
+        fs::create_dir_all(&dir).unwrap();
+
+        let err = resolve(day, &dir, Kind::Puzzle).unwrap_err();
+        assert_eq!(
+            err.candidates,
+            [
+                dir.join("day07.txt"),
+                dir.join("no_space_left_on_device.txt")
+            ]
+        );
+        assert!(err.to_string().starts_with(&format!(
+            "No input for day 7, looked for {}, ",
+            dir.join("day07.txt").display()
+        )));
+
+        fs::write(dir.join("no_space_left_on_device.txt"), "").unwrap();
+        assert_eq!(
+            resolve(day, &dir, Kind::Puzzle).unwrap(),
+            dir.join("no_space_left_on_device.txt")
+        );
+        fs::write(dir.join("day07.txt"), "").unwrap();
+        assert_eq!(
+            resolve(day, &dir, Kind::Puzzle).unwrap(),
+            dir.join("day07.txt")
+        );
+
+        assert!(resolve(day, &dir, Kind::Example).is_err());
+        fs::write(dir.join("day07.example.txt"), "").unwrap();
+        assert_eq!(
+            resolve(day, &dir, Kind::Example).unwrap(),
+            dir.join("day07.example.txt")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
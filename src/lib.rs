@@ -0,0 +1,39 @@
+//! Advent of Code 2022 solutions.
+//!
+//! Every day implements [`Problem`]; [`registry::DAYS`] lists them by day number for the
+//! command line front end in `main.rs`, which drives them.
+//! [`dyn_problem::DynProblem`] erases the per-day types where days are handled uniformly,
+//! and [`single_pass::SinglePass`] solves some days while streaming their input.
+
+pub mod calorie_counting;
+pub mod camp_cleanup;
+mod cancel;
+pub mod cathode_ray_tube;
+pub mod distress_signal;
+pub mod dyn_problem;
+pub mod generate;
+pub mod grid;
+pub mod hill_climbing_algorithm;
+pub mod inputs;
+pub mod monkey_in_the_middle;
+pub mod no_space_left_on_device;
+pub mod params;
+pub mod parse;
+pub mod point;
+pub mod problem;
+pub mod registry;
+pub mod regolith_reservoir;
+pub mod rock_paper_scissors;
+pub mod rope_bridge;
+pub mod rucksack_reorganization;
+pub mod single_pass;
+pub mod supply_stacks;
+pub mod treetop_tree_house;
+pub mod tuning_trouble;
+
+/// Long loops of the solvers call [`checkpoint`], which stops them once the [`Token`]
+/// installed on their thread is cancelled.
+pub use crate::cancel::{checkpoint, Token};
+pub use crate::parse::ParseError;
+pub use crate::problem::{Problem, Solution};
+pub use crate::registry::{Answers, Day, Part, DAYS};
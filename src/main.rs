@@ -1,37 +1,339 @@
-mod calorie_counting;
-mod camp_cleanup;
-mod cathode_ray_tube;
-mod distress_signal;
-mod hill_climbing_algorithm;
-mod monkey_in_the_middle;
-mod no_space_left_on_device;
-mod problem;
-mod regolith_reservoir;
-mod rock_paper_scissors;
-mod rope_bridge;
-mod rucksack_reorganization;
-mod supply_stacks;
-mod treetop_tree_house;
-mod tuning_trouble;
-
-use std::path::Path;
-
-use crate::problem::Problem;
-use crate::regolith_reservoir::RegolithReservoir;
-
-pub fn solver<T: Problem>(
-    filename: impl AsRef<Path>,
-) -> (T::OutputDataFirstPart, Option<T::OutputDataSecondPart>) {
-    let data = T::read_file(filename);
-    let first_answer = T::first_part(data.clone());
-    let second_answer = T::second_part(data);
-
-    (first_answer, second_answer)
-}
+mod bench;
+mod cli;
+mod fetch;
+mod generated;
+mod memory;
+mod output;
+mod repl;
+mod runner;
+mod verify;
+mod visualize;
+mod watch;
+
+use std::path::PathBuf;
+
+use advent_of_code_2022::inputs::{self, Kind};
+use advent_of_code_2022::registry::{Day, Part, DAYS};
+
+use crate::bench::Results;
+use crate::cli::{Command, USAGE};
+use crate::fetch::Fetcher;
+use crate::repl::Session;
+use crate::runner::{Outcome, Profile};
+use crate::verify::{Check, Manifest};
+use crate::visualize::Output;
+
+#[cfg(any(test, feature = "count-allocations"))]
+#[global_allocator]
+static ALLOCATOR: memory::CountingAllocator = memory::CountingAllocator;
 
 fn main() {
-    println!(
-        "{:?}",
-        solver::<RegolithReservoir>("regolith_reservoir.txt")
-    )
+    let command = match Command::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Run {
+            day,
+            part,
+            input,
+            format: Some(format),
+            params,
+            timeout,
+            kind,
+            ..
+        } => {
+            let input = resolve_input(day, input, kind);
+            let results = [(
+                day,
+                runner::with_silent_panics(|| runner::run_day(day, &input, &params, part, timeout)),
+            )];
+            print!(
+                "{}",
+                output::render(&output::records(&results, part), format)
+            );
+
+            if !matches!(results[0].1, Outcome::Solved(_)) {
+                std::process::exit(1);
+            }
+        }
+        Command::Run {
+            day,
+            part,
+            input,
+            params,
+            visualize,
+            timeout,
+            kind,
+            stream,
+            ..
+        } => {
+            let input = resolve_input(day, input, kind);
+            if let Some(output) = visualize {
+                match visualize::export(day, &input, &params, &output) {
+                    Ok(count) => {
+                        if let Output::Files(dir) = output {
+                            println!("Wrote {} frames to {}", count, dir.display());
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            let outcome = if stream {
+                match day.solve_single_pass(&input, &params, part) {
+                    Ok(answers) => Outcome::Solved(Profile {
+                        answers,
+                        timings: Default::default(),
+                        memory: None,
+                    }),
+                    Err(err) => Outcome::Failed(err.to_string()),
+                }
+            } else {
+                runner::with_silent_panics(|| runner::run_day(day, &input, &params, part, timeout))
+            };
+            let answers = match outcome {
+                Outcome::Solved(profile) => profile.answers,
+                Outcome::Failed(reason) => {
+                    eprintln!("{}", reason);
+                    std::process::exit(1);
+                }
+                Outcome::TimedOut(reason) => {
+                    eprintln!("Day {} timed out: {}", day.number, reason);
+                    std::process::exit(1);
+                }
+            };
+
+            println!("Day {}: {}", day.number, day.name);
+            for current in Part::ALL {
+                if current.is_selected(part) {
+                    print_answer(current, answers.get(current));
+                }
+            }
+        }
+        Command::Watch {
+            day,
+            input,
+            params,
+            interval,
+            kind,
+        } => {
+            let input = resolve_input(day, input, kind);
+            watch::watch(day, &input, &params, interval)
+        }
+        Command::Repl {
+            day,
+            input,
+            params,
+            kind,
+        } => {
+            let input = resolve_input(day, input, kind);
+            let mut session = match Session::load(day, &input, params) {
+                Ok(session) => session,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            };
+
+            println!("Loaded {}, type help for the commands", input.display());
+            if let Err(err) = repl::run(&mut session, std::io::stdin().lock(), std::io::stdout()) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        Command::All {
+            dir,
+            format,
+            timeout,
+        } => {
+            let results = runner::run_days(&DAYS, &dir, timeout);
+            match format {
+                Some(format) => print!(
+                    "{}",
+                    output::render(&output::records(&results, None), format)
+                ),
+                None => println!("{}", runner::format_report(&results)),
+            }
+
+            if results
+                .iter()
+                .any(|(_, outcome)| !matches!(outcome, Outcome::Solved(_)))
+            {
+                std::process::exit(1);
+            }
+        }
+        Command::Verify {
+            answers,
+            dir,
+            update: true,
+            timeout,
+        } => {
+            // Days failing this time keep the answers recorded before
+            let mut manifest = if answers.exists() {
+                Manifest::read_file(&answers).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                })
+            } else {
+                Manifest::default()
+            };
+            let results = runner::run_days(&DAYS, &dir, timeout);
+            for (day, reason) in manifest.record(&results) {
+                eprintln!("Day {} ({}) kept: {}", day.number, day.name, reason);
+            }
+
+            if let Err(err) = std::fs::write(&answers, manifest.to_string()) {
+                eprintln!("{}: {}", answers.display(), err);
+                std::process::exit(1);
+            }
+        }
+        Command::Verify {
+            answers,
+            dir,
+            timeout,
+            ..
+        } => {
+            let manifest = match Manifest::read_file(&answers) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            };
+
+            let results = runner::run_days(manifest.days(), &dir, timeout);
+            let checks = verify::check(&manifest, &results);
+            println!("{}", verify::format_checks(&checks));
+
+            if checks
+                .iter()
+                .any(|(_, _, check)| !matches!(check, Check::Passed))
+            {
+                std::process::exit(1);
+            }
+        }
+        Command::Bench {
+            days,
+            dir,
+            config,
+            save,
+            baseline,
+            threshold,
+        } => {
+            let baseline = baseline.map(|baseline| match Results::read_file(baseline) {
+                Ok(baseline) => baseline,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            });
+
+            let (results, failures) = bench::run_days(days, &dir, &config);
+            let (report, regressed) = bench::format_report(&results, baseline.as_ref(), threshold);
+            print!("{}", report);
+            for (day, reason) in &failures {
+                println!("Day {} ({}) failed: {}", day.number, day.name, reason);
+            }
+
+            if let Some(save) = save {
+                if let Err(err) = std::fs::write(&save, results.to_string()) {
+                    eprintln!("{}: {}", save.display(), err);
+                    std::process::exit(1);
+                }
+            }
+            if regressed || !failures.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Generate {
+            days,
+            dir,
+            seed,
+            size,
+        } => {
+            let seed = seed.unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+            });
+
+            match generated::write_days(days, &dir, seed, size) {
+                Ok(written) => {
+                    println!("Seed: {}", seed);
+                    for (day, path) in written {
+                        println!("Day {} ({}): {}", day.number, day.name, path.display());
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{}: {}", dir.display(), err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Fetch {
+            days,
+            dir,
+            session_file,
+        } => {
+            let session = match fetch::session(&session_file) {
+                Ok(session) => session,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            };
+            let base_url = fetch::base_url();
+            let fetcher = Fetcher {
+                transport: fetch::transport_for(&base_url),
+                base_url,
+                session,
+                dir,
+            };
+
+            let mut failed = false;
+            for day in days {
+                match fetcher.fetch(day) {
+                    Ok((path, true)) => {
+                        println!("Day {}: downloaded {}", day.number, path.display())
+                    }
+                    Ok((path, false)) => println!("Day {}: cached {}", day.number, path.display()),
+                    Err(err) => {
+                        eprintln!("Day {}: {}", day.number, err);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        Command::Help => println!("{}", USAGE),
+    }
+}
+
+/// The given input, or else the day's input found in the inputs directory.
+fn resolve_input(day: &Day, input: Option<PathBuf>, kind: Kind) -> PathBuf {
+    input.unwrap_or_else(|| {
+        inputs::resolve(day, &inputs::dir(), kind).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+    })
+}
+
+fn print_answer(part: Part, answer: Option<&String>) {
+    let part = part.number();
+    match answer {
+        Some(answer) if answer.contains('\n') => println!("Part {}:\n{}", part, answer),
+        Some(answer) => println!("Part {}: {}", part, answer),
+        None => println!("Part {}: -", part),
+    }
 }
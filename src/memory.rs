@@ -0,0 +1,172 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+// Per thread, so that workers abandoned after a timeout are not charged to later days.
+// Memory freed by another thread than the one allocating it can take `CURRENT` below zero.
+thread_local! {
+    static CURRENT: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+    static TOTAL: Cell<usize> = const { Cell::new(0) };
+}
+
+/// System allocator that counts the bytes going through it.
+///
+/// The binary installs it as the global allocator with the `count-allocations` feature,
+/// and always in tests; without it [`is_counting`] is `false` and [`measure`] sees nothing.
+#[cfg_attr(not(feature = "count-allocations"), allow(dead_code))]
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        freed(layout.size());
+    }
+
+    /// Counted as a new allocation of `new_size` bytes alongside the old one, which is what
+    /// a moving reallocation costs at its peak.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            allocated(new_size);
+            freed(layout.size());
+        }
+        new_ptr
+    }
+}
+
+// The counters have no destructor, so they are still there while a thread shuts down;
+// `try_with` only guards against that changing.
+#[cfg_attr(not(feature = "count-allocations"), allow(dead_code))]
+fn allocated(size: usize) {
+    if !COUNTING.load(Ordering::Relaxed) {
+        COUNTING.store(true, Ordering::Relaxed);
+    }
+    let _ = TOTAL.try_with(|total| total.set(total.get().wrapping_add(size)));
+    let _ = CURRENT.try_with(|current| {
+        current.set(current.get().wrapping_add(size as isize));
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(current.get())));
+    });
+}
+
+#[cfg_attr(not(feature = "count-allocations"), allow(dead_code))]
+fn freed(size: usize) {
+    let _ = CURRENT.try_with(|current| current.set(current.get().wrapping_sub(size as isize)));
+}
+
+/// Whether [`CountingAllocator`] is the global allocator.
+pub fn is_counting() -> bool {
+    // Nothing gets this far without allocating, so no bytes means no counting
+    COUNTING.load(Ordering::Relaxed)
+}
+
+/// Bytes allocated while running a piece of code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// Most bytes alive at once, on top of those alive before.
+    pub peak: usize,
+    /// All bytes allocated, freed or not.
+    pub total: usize,
+}
+
+/// Runs `f`, counting what it allocates.
+///
+/// Only allocations of the current thread count, so `f` should not hand work to other
+/// threads; other threads allocating in the meantime are left out.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Usage) {
+    let base = CURRENT.with(Cell::get);
+    PEAK.with(|peak| peak.set(base));
+    let total = TOTAL.with(Cell::get);
+
+    let result = f();
+
+    let usage = Usage {
+        peak: PEAK.with(Cell::get).saturating_sub(base).max(0) as usize,
+        total: TOTAL.with(Cell::get).wrapping_sub(total),
+    };
+    (result, usage)
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_allocations() {
+        const SIZE: usize = 1 << 20;
+        assert!(is_counting());
+
+        let (_, usage) = measure(|| {
+            let mut buffer = Vec::<u8>::with_capacity(SIZE);
+            buffer.push(1);
+            drop(buffer);
+            vec![0u8; SIZE]
+        });
+
+        assert!(usage.peak >= SIZE);
+        assert!(usage.total >= 2 * SIZE);
+    }
+
+    #[test]
+    fn ignore_other_threads() {
+        const SIZE: usize = 1 << 24;
+        let (started, start) = std::sync::mpsc::channel();
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let other = std::thread::spawn(move || {
+            let buffer = vec![1u8; SIZE];
+            started.send(()).unwrap();
+            stopped.recv().unwrap();
+            buffer.len()
+        });
+
+        let ((), usage) = measure(|| {
+            start.recv().unwrap();
+            stop.send(()).unwrap();
+        });
+        assert_eq!(other.join().unwrap(), SIZE);
+
+        assert!(usage.total < SIZE, "{:?}", usage);
+        assert!(usage.peak < SIZE, "{:?}", usage);
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 << 20), "3.0 MiB");
+    }
+}
@@ -1,8 +1,10 @@
+use crate::cancel;
+use crate::generate::{Generate, Rng};
+use crate::params::{self, Params};
+use crate::parse::{self, Line, ParseError};
 use crate::problem::Problem;
 
-use std::cell::RefCell;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
 #[derive(Clone, Debug)]
 pub enum Operation {
@@ -12,11 +14,11 @@ pub enum Operation {
 }
 
 impl Operation {
-    fn execute(&self, old: u128) -> u128 {
+    fn execute(&self, old: u128) -> Option<u128> {
         match self {
-            Operation::Mul(op) => old * (*op),
-            Operation::Add(op) => old + (*op),
-            Operation::Sqr => old * old,
+            Operation::Mul(op) => old.checked_mul(*op),
+            Operation::Add(op) => old.checked_add(*op),
+            Operation::Sqr => old.checked_mul(old),
         }
     }
 }
@@ -30,44 +32,135 @@ pub struct WorryTest {
 
 #[derive(Clone, Debug)]
 pub struct Monkey {
-    items: RefCell<Vec<u128>>,
+    items: Vec<u128>,
     operation: Operation,
     test: WorryTest,
 }
 
+#[derive(Debug, Clone)]
+pub struct MonkeyParams {
+    pub first_rounds: usize,
+    /// Worry levels are divided by it after each inspection in the first part.
+    pub relief: u128,
+    pub second_rounds: usize,
+}
+
+impl Default for MonkeyParams {
+    fn default() -> Self {
+        Self {
+            first_rounds: 20,
+            relief: 3,
+            second_rounds: 10_000,
+        }
+    }
+}
+
+impl Params for MonkeyParams {
+    const NAMES: &'static [&'static str] = &["first_rounds", "relief", "second_rounds"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "first_rounds" => self.first_rounds = params::parse_value(name, value)?,
+            "relief" => match params::parse_value(name, value)? {
+                relief @ 1.. => self.relief = relief,
+                _ => return Err("relief should be positive".to_string()),
+            },
+            "second_rounds" => self.second_rounds = params::parse_value(name, value)?,
+            _ => return Err(Self::unknown(name)),
+        }
+
+        Ok(())
+    }
+}
+
 pub struct MonkeyInTheMiddle;
 
 impl MonkeyInTheMiddle {
-    fn get_monkey_business(input: Vec<Monkey>, rounds: usize, divider: u128) -> u128 {
+    fn parse_monkey(section: &[Line]) -> Result<Monkey, ParseError> {
+        let [header, items, operation, test, if_true, if_false] = section else {
+            return Err(section[0].error("expected 6 lines describing a monkey"));
+        };
+        header.fields::<1>(header.text, "Monkey {}:")?;
+
+        let items = items.integers::<u128>(items.value("Starting items")?)?;
+
+        let expression = operation.value("Operation")?;
+        let [operator, operand] = operation.fields(expression, "new = old {} {}")?;
+        let operation = match (operator, operand) {
+            ("+", operand) => Operation::Add(operation.parse(operand)?),
+            ("*", "old") => Operation::Sqr,
+            ("*", operand) => Operation::Mul(operation.parse(operand)?),
+            _ => {
+                return Err(
+                    operation.error_at(operator, format!("unknown operation '{}'", operator))
+                )
+            }
+        };
+
+        let [divider] = test.fields(test.value("Test")?, "divisible by {}")?;
+        let divider = test.parse(divider)?;
+        if divider == 0 {
+            return Err(test.error("divider should be positive"));
+        }
+        let throw_target = |line: &Line, key| -> Result<usize, ParseError> {
+            let [target] = line.fields(line.value(key)?, "throw to monkey {}")?;
+            line.parse(target)
+        };
+
+        Ok(Monkey {
+            items,
+            operation,
+            test: WorryTest {
+                divider,
+                true_monkey: throw_target(if_true, "If true")?,
+                false_monkey: throw_target(if_false, "If false")?,
+            },
+        })
+    }
+
+    /// Items inspected by every monkey, or `None` if a worry level overflows or, without
+    /// relief, the product of the dividers does.
+    ///
+    /// Without relief levels are kept modulo the product of all dividers, which preserves
+    /// every test; with relief they are exact, as the division does not commute with it.
+    fn get_activity(input: &[Monkey], rounds: usize, divider: u128) -> Option<Vec<u128>> {
         let mut activity = vec![0u128; input.len()];
-        let overflow_reducer = input.iter().fold(1, |acc, el| acc * el.test.divider);
+        let mut items = input
+            .iter()
+            .map(|monkey| monkey.items.clone())
+            .collect::<Vec<_>>();
+        let overflow_reducer = input
+            .iter()
+            .try_fold(1u128, |acc, el| acc.checked_mul(el.test.divider));
         for _ in 0..rounds {
+            cancel::checkpoint();
             for (i, monkey) in input.iter().enumerate() {
-                let mut items = monkey.items.borrow_mut();
-                while items.len() > 0 {
-                    let item = items.remove(0);
-                    let new_level = monkey.operation.execute(item) / divider;
-                    let new_level = new_level % overflow_reducer;
-                    if new_level % monkey.test.divider == 0 {
-                        input[monkey.test.true_monkey]
-                            .items
-                            .borrow_mut()
-                            .push(new_level);
+                for item in std::mem::take(&mut items[i]) {
+                    let mut new_level = monkey.operation.execute(item)? / divider;
+                    if divider == 1 {
+                        new_level %= overflow_reducer?;
+                    }
+                    if new_level.is_multiple_of(monkey.test.divider) {
+                        items[monkey.test.true_monkey].push(new_level);
                     } else {
-                        input[monkey.test.false_monkey]
-                            .items
-                            .borrow_mut()
-                            .push(new_level);
+                        items[monkey.test.false_monkey].push(new_level);
                     }
 
                     activity[i] += 1;
                 }
             }
         }
+
+        Some(activity)
+    }
+
+    /// `None` when a worry level or the result overflows.
+    fn get_monkey_business(input: &[Monkey], rounds: usize, divider: u128) -> Option<u128> {
+        let mut activity = Self::get_activity(input, rounds, divider)?;
         activity.sort();
         activity.reverse();
 
-        activity[0] * activity[1]
+        activity[0].checked_mul(activity[1])
     }
 }
 
@@ -75,82 +168,349 @@ impl Problem for MonkeyInTheMiddle {
     type InputData = Vec<Monkey>;
     type OutputDataFirstPart = u128;
     type OutputDataSecondPart = u128;
+    type Params = MonkeyParams;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        //TODO: Split parse into function. I'm too lazy
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .collect::<Vec<_>>()
-            .chunks(7)
-            .fold(vec![], |mut monkeys, chunks| {
-                let (_, items) = chunks[1].split_once(':').unwrap();
-                let items = items
-                    .trim()
-                    .split(", ")
-                    .map(|el| el.parse::<u128>().unwrap())
-                    .collect::<Vec<_>>();
-
-                let (_, operation) = chunks[2].split_once('=').unwrap();
-                let tokens = operation.trim().split(' ').collect::<Vec<_>>();
-                let operation = match tokens[1] {
-                    "+" => {
-                        let operand = tokens[2].parse().unwrap();
-                        Operation::Add(operand)
-                    }
-                    "*" => {
-                        let operand = tokens[2].parse();
-                        match operand {
-                            Ok(operand) => Operation::Mul(operand),
-                            Err(_) => Operation::Sqr,
-                        }
-                    }
-                    _ => panic!("Wrong operation"),
-                };
-
-                let divider = chunks[3]
-                    .split(' ')
-                    .last()
-                    .unwrap()
-                    .parse::<u128>()
-                    .unwrap();
-                let true_monkey = chunks[4]
-                    .split(' ')
-                    .last()
-                    .unwrap()
-                    .parse::<usize>()
-                    .unwrap();
-                let false_monkey = chunks[5]
-                    .split(' ')
-                    .last()
-                    .unwrap()
-                    .parse::<usize>()
-                    .unwrap();
-
-                let monkey = Monkey {
-                    items: RefCell::new(items),
-                    operation,
-                    test: WorryTest {
-                        divider,
-                        true_monkey,
-                        false_monkey,
-                    },
-                };
-                monkeys.push(monkey);
-
-                monkeys
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut monkeys = vec![];
+        parse::for_each_section(reader, |section| {
+            monkeys.push(MonkeyInTheMiddle::parse_monkey(section)?);
+            Ok(())
+        })?;
+
+        // Monkey business multiplies the activity of the two most active monkeys
+        if monkeys.len() < 2 {
+            return Err(ParseError::input("expected at least two monkeys"));
+        }
+        if let Some(target) = monkeys
+            .iter()
+            .flat_map(|monkey| [monkey.test.true_monkey, monkey.test.false_monkey])
+            .find(|&target| target >= monkeys.len())
+        {
+            return Err(ParseError::input(format!(
+                "throw target monkey {} does not exist",
+                target
+            )));
+        }
+
+        Ok(monkeys)
+    }
+
+    fn first_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        MonkeyInTheMiddle::get_monkey_business(input, params.first_rounds, params.relief)
+    }
+
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        MonkeyInTheMiddle::get_monkey_business(input, params.second_rounds, 1)
+    }
+
+    /// Every monkey's starting items, rules and inspections over the first part's rounds.
+    fn describe(input: &Self::InputData, params: &Self::Params) -> Option<String> {
+        let activity = Self::get_activity(input, params.first_rounds, params.relief);
+
+        let monkeys = input.iter().enumerate().map(|(i, monkey)| {
+            let items = monkey
+                .items
+                .iter()
+                .map(u128::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let operation = match monkey.operation {
+                Operation::Mul(op) => format!("old * {}", op),
+                Operation::Add(op) => format!("old + {}", op),
+                Operation::Sqr => "old * old".to_string(),
+            };
+            let inspected = activity
+                .as_ref()
+                .map_or("overflow".to_string(), |activity| activity[i].to_string());
+
+            format!(
+                "Monkey {}: [{}] new = {}, divisible by {} ? {} : {}, inspected {} in {} rounds",
+                i,
+                items,
+                operation,
+                monkey.test.divider,
+                monkey.test.true_monkey,
+                monkey.test.false_monkey,
+                inspected,
+                params.first_rounds
+            )
+        });
+
+        Some(monkeys.collect::<Vec<_>>().join("\n"))
+    }
+}
+
+impl Generate for MonkeyInTheMiddle {
+    /// `size` monkeys, between 2 and 15, one of which squares the worry level.
+    ///
+    /// Dividers are distinct primes whose product fits into `u64`, so squaring a level reduced
+    /// by it can not overflow. Like puzzle inputs, the notes keep exact levels within `u128`
+    /// for the first 20 rounds; notes that do not are drawn again. Few monkeys pass items
+    /// back and forth too often for squaring to ever fit, so after a number of attempts every
+    /// monkey adds to the level instead, which always fits.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        const ATTEMPTS: usize = 100;
+        let mut primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+        let count = size.clamp(2, primes.len());
+        let target = |rng: &mut Rng, except: &[usize]| loop {
+            let target = rng.below(count);
+            if !except.contains(&target) {
+                break target;
+            }
+        };
+
+        for attempt in 0.. {
+            let adding = attempt >= ATTEMPTS;
+            rng.shuffle(&mut primes);
+            let squaring = rng.below(count);
+            let notes = (0..count)
+                .map(|i| {
+                    let items = (0..rng.range(1..=8))
+                        .map(|_| rng.range(50..=99).to_string())
+                        .collect::<Vec<_>>();
+                    let operation = match rng.below(3) {
+                        _ if adding => format!("old + {}", rng.range(1..=8)),
+                        _ if i == squaring => "old * old".to_string(),
+                        0 => format!("old * {}", rng.range(2..=19)),
+                        _ => format!("old + {}", rng.range(1..=8)),
+                    };
+                    let true_monkey = target(rng, &[i]);
+                    let false_monkey = if count > 2 {
+                        target(rng, &[i, true_monkey])
+                    } else {
+                        true_monkey
+                    };
+
+                    [
+                        format!("Monkey {}:", i),
+                        format!("  Starting items: {}", items.join(", ")),
+                        format!("  Operation: new = {}", operation),
+                        format!("  Test: divisible by {}", primes[i]),
+                        format!("    If true: throw to monkey {}", true_monkey),
+                        format!("    If false: throw to monkey {}", false_monkey),
+                    ]
+                    .join("\n")
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+
+            let monkeys = Self::parse_str(&notes).expect("generated notes should parse");
+            if Self::get_activity(&monkeys, 20, 3).is_some() {
+                return notes;
+            }
+        }
+
+        unreachable!("notes of adding monkeys never overflow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1";
+
+    #[test]
+    fn first_part_example() {
+        let input = MonkeyInTheMiddle::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            MonkeyInTheMiddle::first_part(&input, &Default::default()),
+            Some(10605)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = MonkeyInTheMiddle::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            MonkeyInTheMiddle::second_part(&input, &Default::default()),
+            Some(2713310158)
+        );
+    }
+
+    #[test]
+    fn reject_fewer_than_two_monkeys() {
+        let err = MonkeyInTheMiddle::parse_str("").unwrap_err();
+        assert_eq!(err.message, "expected at least two monkeys");
+        let one = EXAMPLE.split("\n\n").next().unwrap();
+        let err = MonkeyInTheMiddle::parse_str(one).unwrap_err();
+        assert_eq!(err.message, "expected at least two monkeys");
+    }
+
+    #[test]
+    fn overflow_of_dividers() {
+        let notes = (0..40)
+            .map(|i| {
+                format!(
+                    "Monkey {}:\n  Starting items: 1\n  Operation: new = old + 1\n  \
+                     Test: divisible by 1000003\n    If true: throw to monkey 0\n    \
+                     If false: throw to monkey 1",
+                    i
+                )
             })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let input = MonkeyInTheMiddle::parse_str(&notes).unwrap();
+
+        assert_eq!(
+            MonkeyInTheMiddle::second_part(&input, &Default::default()),
+            None
+        );
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        const ROUNDS: usize = 20;
-        MonkeyInTheMiddle::get_monkey_business(input, ROUNDS, 3)
+    #[test]
+    fn overflow_with_little_relief() {
+        let input = MonkeyInTheMiddle::parse_str(EXAMPLE).unwrap();
+        let params = MonkeyParams {
+            first_rounds: 1_000,
+            relief: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(MonkeyInTheMiddle::first_part(&input, &params), None);
+    }
+
+    #[test]
+    fn generate_few_monkeys() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for size in 0..=3 {
+                let notes = MonkeyInTheMiddle::generate(&mut Rng::new(size as u64), size);
+                let input = MonkeyInTheMiddle::parse_str(&notes).unwrap();
+                let answer = MonkeyInTheMiddle::first_part(&input, &Default::default());
+                sender.send((size, answer)).unwrap();
+            }
+        });
+
+        for size in 0..=3 {
+            let (generated, answer) = receiver
+                .recv_timeout(std::time::Duration::from_secs(10))
+                .unwrap_or_else(|_| panic!("generating {} monkeys took too long", size));
+            assert_eq!(generated, size);
+            assert!(answer.is_some());
+        }
+    }
+
+    /// Keeps exact worry levels with relief and, without it, the remainder by every divider
+    /// separately instead of by their product.
+    fn reference(input: &str) -> (u128, Option<u128>) {
+        let monkeys = input
+            .split("\n\n")
+            .map(|notes| {
+                let lines = notes.lines().collect::<Vec<_>>();
+                let last = |line: &str| line.rsplit(' ').next().unwrap().to_string();
+                let items = lines[1].split_once(": ").unwrap().1;
+                (
+                    items
+                        .split(", ")
+                        .map(|item| item.parse::<u128>().unwrap())
+                        .collect::<Vec<_>>(),
+                    lines[2].split_once("= old ").unwrap().1.to_string(),
+                    last(lines[3]).parse::<u128>().unwrap(),
+                    last(lines[4]).parse::<usize>().unwrap(),
+                    last(lines[5]).parse::<usize>().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let apply = |operation: &str, old: u128| match operation.split_once(' ').unwrap() {
+            ("*", "old") => old * old,
+            ("*", value) => old * value.parse::<u128>().unwrap(),
+            (_, value) => old + value.parse::<u128>().unwrap(),
+        };
+        let business = |mut activity: Vec<u128>| {
+            activity.sort_by(|a, b| b.cmp(a));
+            activity[0] * activity[1]
+        };
+
+        let mut items = monkeys
+            .iter()
+            .map(|monkey| monkey.0.clone())
+            .collect::<Vec<_>>();
+        let mut activity = vec![0; monkeys.len()];
+        for _ in 0..20 {
+            for (i, (_, operation, divider, if_true, if_false)) in monkeys.iter().enumerate() {
+                for item in std::mem::take(&mut items[i]) {
+                    let level = apply(operation, item) / 3;
+                    items[if level % divider == 0 {
+                        *if_true
+                    } else {
+                        *if_false
+                    }]
+                    .push(level);
+                    activity[i] += 1;
+                }
+            }
+        }
+        let first = business(activity);
+
+        let dividers = monkeys.iter().map(|monkey| monkey.2).collect::<Vec<_>>();
+        let mut items = monkeys
+            .iter()
+            .map(|monkey| {
+                let items = monkey.0.iter();
+                items
+                    .map(|&item| dividers.iter().map(|d| item % d).collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut activity = vec![0; monkeys.len()];
+        for _ in 0..10_000 {
+            for (i, (_, operation, _, if_true, if_false)) in monkeys.iter().enumerate() {
+                for item in std::mem::take(&mut items[i]) {
+                    let residues = item
+                        .iter()
+                        .zip(&dividers)
+                        .map(|(&residue, d)| apply(operation, residue) % d)
+                        .collect::<Vec<_>>();
+                    let target = if residues[i] == 0 {
+                        *if_true
+                    } else {
+                        *if_false
+                    };
+                    items[target].push(residues);
+                    activity[i] += 1;
+                }
+            }
+        }
+
+        (first, Some(business(activity)))
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        const ROUNDS: usize = 10000;
-        Some(MonkeyInTheMiddle::get_monkey_business(input, ROUNDS, 1))
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<MonkeyInTheMiddle>(3, reference);
     }
 }
@@ -1,96 +1,191 @@
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
+use crate::generate::{Generate, Rng};
 use crate::no_space_left_on_device::tree::{Arena, Cursor};
+use crate::params::{self, Params};
+use crate::parse::{self, ParseError};
 use crate::problem::Problem;
 
+#[derive(Debug, Clone)]
+pub struct DiskParams {
+    /// Directories smaller than this are summed in the first part.
+    pub small_dir: u64,
+    pub total_space: u64,
+    /// Unused space the update needs.
+    pub need_space: u64,
+}
+
+impl Default for DiskParams {
+    fn default() -> Self {
+        Self {
+            small_dir: 100_000,
+            total_space: 70_000_000,
+            need_space: 30_000_000,
+        }
+    }
+}
+
+impl Params for DiskParams {
+    const NAMES: &'static [&'static str] = &["small_dir", "total_space", "need_space"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "small_dir" => self.small_dir = params::parse_value(name, value)?,
+            "total_space" => self.total_space = params::parse_value(name, value)?,
+            "need_space" => self.need_space = params::parse_value(name, value)?,
+            _ => return Err(Self::unknown(name)),
+        }
+
+        Ok(())
+    }
+}
+
 pub struct NoSpaceLeftOnDevice;
 
 impl Problem for NoSpaceLeftOnDevice {
     type InputData = Cursor<'static, u64>;
     type OutputDataFirstPart = u64;
     type OutputDataSecondPart = u64;
+    type Params = DiskParams;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        //Skip first line "$ cd /"
-        let lines = reader
-            .lines()
-            .map(Result::unwrap)
-            .skip(1)
-            .collect::<Vec<_>>();
-
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let lines = parse::read_lines(reader)?;
         let arena = Arena::new();
         let root_id = arena.new_dir("/", None);
         let mut cursor = Cursor::with_arena(root_id, arena);
 
-        for line in lines {
-            if line.starts_with('$') {
-                let mut args = line.split(' ').skip(1);
+        //Skip first line "$ cd /"
+        for line in parse::numbered(&lines).skip(1) {
+            if let Some(command) = line.text.strip_prefix("$ ") {
+                let mut args = command.split(' ');
                 match args.next().unwrap() {
                     "cd" => {
-                        let dir_name = args.next().unwrap();
+                        let dir_name = args
+                            .next()
+                            .ok_or_else(|| line.error("expected directory name"))?;
 
                         if dir_name == ".." {
-                            cursor.cd_parent();
+                            cursor.cd_parent()
                         } else {
-                            cursor.cd(dir_name);
+                            cursor.cd(dir_name)
                         }
+                        .map_err(|err| line.error_at(dir_name, err))?;
                     }
                     "ls" => {
                         //Useless?
                     }
-                    _ => {}
+                    unknown => {
+                        return Err(line.error_at(unknown, format!("unknown command '{}'", unknown)))
+                    }
                 }
             } else {
-                let (left, right) = line.split_once(' ').unwrap();
+                let (left, right) = line.split_once(line.text, " ")?;
 
                 if left == "dir" {
                     cursor.new_dir(right);
                 } else {
-                    cursor.new_file(right, left.parse::<u64>().unwrap());
+                    cursor.new_file(right, line.parse::<u64>(left)?);
                 }
             }
         }
 
-        cursor
+        Ok(cursor)
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        let dirs = input.get_dirs_with_space_pred(100_000, &|need, el| el < need);
+    fn first_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        let dirs = input.get_dirs_with_space_pred(params.small_dir, &|need, el| el < need);
 
-        dirs.into_iter()
-            .fold(0, |acc, el| acc + input.get_space(el))
+        Some(
+            dirs.into_iter()
+                .fold(0, |acc, el| acc + input.get_space(el)),
+        )
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        const TOTAL_SPACE: u64 = 70_000_000;
-        const NEED_SPACE: u64 = 30_000_000;
-
-        let mut input = input;
-        input.cd_root();
-
-        let used_space = input.get_current_cursor_space();
-        let available_space = TOTAL_SPACE - used_space;
-        let need_to_free = NEED_SPACE - available_space;
-
-        let space_to_free = input
-            .get_dirs_with_space_pred(need_to_free, &|need, el| el > need)
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        let used_space = input.get_root_space();
+        let available_space = params.total_space.saturating_sub(used_space);
+        let need_to_free = params.need_space.saturating_sub(available_space);
+
+        // `None` when even the root is too small for the update
+        input
+            .get_dirs_with_space_pred(need_to_free, &|need, el| el >= need)
             .into_iter()
             .map(|el| input.get_space(el))
             .min()
-            .unwrap();
+    }
+
+    fn describe(input: &Self::InputData, _params: &Self::Params) -> Option<String> {
+        Some(input.render())
+    }
+}
+
+impl Generate for NoSpaceLeftOnDevice {
+    /// A terminal transcript exploring `size` directories below the root.
+    ///
+    /// File sizes add up to between 40M and 70M, so the disk is never overfull and some
+    /// space always has to be freed.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut children = vec![vec![]];
+        for dir in 1..=size {
+            children[rng.below(dir)].push(dir);
+            children.push(vec![]);
+        }
+        let weights = (0..=size)
+            .map(|dir| {
+                let files = rng.range(if dir == 0 { 1 } else { 0 }..=4);
+                (0..files).map(|_| rng.range(1..=1_000) as u64).collect()
+            })
+            .collect::<Vec<Vec<u64>>>();
+
+        let used = rng.range(40_000_001..=70_000_000) as u64;
+        let total_weight = weights.iter().flatten().sum::<u64>();
+        let mut files = weights
+            .iter()
+            .map(|dir| {
+                dir.iter()
+                    .map(|weight| weight * used / total_weight)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        files[0][0] += used - files.iter().flatten().sum::<u64>();
+
+        fn explore(dir: usize, children: &[Vec<usize>], files: &[Vec<u64>], out: &mut Vec<String>) {
+            out.push("$ ls".to_string());
+            out.extend(children[dir].iter().map(|child| format!("dir d{}", child)));
+            out.extend(
+                files[dir]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, size)| format!("{} f{}.dat", size, i)),
+            );
+            for &child in &children[dir] {
+                out.push(format!("$ cd d{}", child));
+                explore(child, children, files, out);
+                out.push("$ cd ..".to_string());
+            }
+        }
 
-        Some(space_to_free)
+        let mut transcript = vec!["$ cd /".to_string()];
+        explore(0, &children, &files, &mut transcript);
+
+        transcript.join("\n")
     }
 }
 
 pub mod tree {
     use std::borrow::Cow;
     use std::cell::RefCell;
+    use std::fmt::Display;
     use std::ops::Add;
 
+    use crate::cancel;
+
     pub type NodeId = usize;
 
     #[allow(dead_code)]
@@ -110,7 +205,10 @@ pub mod tree {
 
     impl<T: Clone> Node<T> {
         fn add_child(&mut self, child_id: NodeId) {
-            let Node::Dir { children, metadata, .. } = self else {
+            let Node::Dir {
+                children, metadata, ..
+            } = self
+            else {
                 panic!("Trying add child to file")
             };
 
@@ -141,6 +239,12 @@ pub mod tree {
         nodes: RefCell<Vec<Node<T>>>,
     }
 
+    impl<T> Default for Arena<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl<T> Arena<T> {
         pub fn new() -> Self {
             Self {
@@ -185,7 +289,6 @@ pub mod tree {
         arena: Cow<'arena, Arena<T>>,
     }
 
-    #[allow(dead_code)]
     impl<'arena, T: Clone> Cursor<'arena, T> {
         pub fn new(point: NodeId, arena: &'arena Arena<T>) -> Self {
             Self {
@@ -207,21 +310,22 @@ pub mod tree {
             self.dir = self.root_id;
         }
 
-        pub fn cd_parent(&mut self) {
+        pub fn cd_parent(&mut self) -> Result<(), String> {
             let Node::Dir { parent, .. } = &self.arena.nodes.borrow()[self.dir] else {
-                panic!("Cursor point on file")
+                return Err("Cursor point on file".to_string());
             };
 
             let Some(parent) = parent else {
-                panic!("Cursor point on root")
+                return Err("Cursor point on root".to_string());
             };
 
             self.dir = *parent;
+            Ok(())
         }
 
-        pub fn cd(&mut self, dir_name: &str) {
+        pub fn cd(&mut self, dir_name: &str) -> Result<(), String> {
             let Node::Dir { children, .. } = &self.arena.nodes.borrow()[self.dir] else {
-                panic!("Cursor point on file")
+                return Err("Cursor point on file".to_string());
             };
 
             let Some(child_id) = children.iter().find(|&&id| {
@@ -231,10 +335,11 @@ pub mod tree {
                     false
                 }
             }) else {
-                panic!("No childs with name {}", dir_name)
+                return Err(format!("No childs with name {}", dir_name));
             };
 
             self.dir = *child_id;
+            Ok(())
         }
 
         pub fn new_dir(&self, name: &str) {
@@ -263,6 +368,7 @@ pub mod tree {
                     if let Some(data) = *metadata {
                         return data;
                     }
+                    cancel::checkpoint();
 
                     let new_metadata = children
                         .iter()
@@ -280,6 +386,45 @@ pub mod tree {
             self.get_space(self.dir)
         }
 
+        pub fn get_root_space(&self) -> T {
+            self.get_space(self.root_id)
+        }
+
+        /// Indented listing of the whole tree with the size of every node, the way the
+        /// puzzle shows it.
+        pub fn render(&self) -> String
+        where
+            T: Display,
+        {
+            let mut lines = vec![];
+            self.render_node(self.root_id, 0, &mut lines);
+
+            lines.join("\n")
+        }
+
+        fn render_node(&self, node_id: NodeId, depth: usize, lines: &mut Vec<String>)
+        where
+            T: Display,
+        {
+            let indent = "  ".repeat(depth);
+            match &self.arena.nodes.borrow()[node_id] {
+                Node::Dir { name, children, .. } => {
+                    lines.push(format!(
+                        "{}- {} (dir, size={})",
+                        indent,
+                        name,
+                        self.get_space(node_id)
+                    ));
+                    for &child in children {
+                        self.render_node(child, depth + 1, lines);
+                    }
+                }
+                Node::File { name, data } => {
+                    lines.push(format!("{}- {} (file, size={})", indent, name, data))
+                }
+            }
+        }
+
         fn get_child_with_space_pred_child(
             &self,
             node_id: NodeId,
@@ -287,6 +432,7 @@ pub mod tree {
             func: &impl Fn(T, T) -> bool,
             dirs: &mut Vec<NodeId>,
         ) {
+            cancel::checkpoint();
             let node_space = self.get_space(node_id);
             let node = &self.arena.nodes.borrow()[node_id];
 
@@ -325,3 +471,105 @@ pub mod tree {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
+    #[test]
+    fn first_part_example() {
+        let input = NoSpaceLeftOnDevice::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            NoSpaceLeftOnDevice::first_part(&input, &Default::default()),
+            Some(95437)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = NoSpaceLeftOnDevice::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            NoSpaceLeftOnDevice::second_part(&input, &Default::default()),
+            Some(24933642)
+        );
+    }
+
+    #[test]
+    fn update_larger_than_the_disk() {
+        let input = NoSpaceLeftOnDevice::parse_str(EXAMPLE).unwrap();
+        let params = DiskParams {
+            need_space: 80_000_000,
+            ..Default::default()
+        };
+
+        assert_eq!(NoSpaceLeftOnDevice::second_part(&input, &params), None);
+    }
+
+    /// Adds every file to all directories on the current path, no tree involved.
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        let mut path = vec![];
+        let mut sizes = std::collections::HashMap::<Vec<&str>, u64>::new();
+        for line in input.lines() {
+            match line.split(' ').collect::<Vec<_>>()[..] {
+                ["$", "cd", "/"] => path.clear(),
+                ["$", "cd", ".."] => {
+                    path.pop();
+                }
+                ["$", "cd", dir] => path.push(dir),
+                ["$", "ls"] => {}
+                ["dir", dir] => {
+                    let mut dir_path = path.clone();
+                    dir_path.push(dir);
+                    sizes.entry(dir_path).or_default();
+                }
+                [size, _] => {
+                    let size = size.parse::<u64>().unwrap();
+                    for depth in 0..=path.len() {
+                        *sizes.entry(path[..depth].to_vec()).or_default() += size;
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        let need_to_free = sizes[&vec![]] - 40_000_000;
+        (
+            sizes.values().filter(|&&size| size < 100_000).sum(),
+            sizes
+                .values()
+                .copied()
+                .filter(|&size| size >= need_to_free)
+                .min(),
+        )
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<NoSpaceLeftOnDevice>(40, reference);
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use advent_of_code_2022::registry::{Day, Part};
+
+use crate::runner::{Outcome, Profile};
+
+/// Machine-readable output format; the human-readable text output is the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("Unknown format '{}', expected json or csv", s)),
+        }
+    }
+}
+
+/// A single day/part result, the unit of machine-readable output.
+#[derive(Debug, Clone)]
+pub struct Record<'a> {
+    pub day: &'a Day,
+    pub part: Part,
+    pub answer: Option<&'a str>,
+    pub parse_time: Option<Duration>,
+    pub solve_time: Option<Duration>,
+    pub error: Option<&'a str>,
+}
+
+/// Flattens results into one record per part, optionally only for `part`.
+pub fn records<'a>(results: &'a [(&'static Day, Outcome)], part: Option<Part>) -> Vec<Record<'a>> {
+    let mut records = vec![];
+
+    for (day, outcome) in results {
+        for current in Part::ALL {
+            if part.is_some_and(|part| part != current) {
+                continue;
+            }
+
+            records.push(match outcome {
+                Outcome::Solved(Profile {
+                    answers, timings, ..
+                }) => Record {
+                    day,
+                    part: current,
+                    answer: answers.get(current).map(String::as_str),
+                    parse_time: Some(timings.parse),
+                    solve_time: Some(match current {
+                        Part::First => timings.first,
+                        Part::Second => timings.second,
+                    }),
+                    error: None,
+                },
+                Outcome::Failed(reason) | Outcome::TimedOut(reason) => Record {
+                    day,
+                    part: current,
+                    answer: None,
+                    parse_time: None,
+                    solve_time: None,
+                    error: Some(reason),
+                },
+            });
+        }
+    }
+
+    records
+}
+
+/// JSON array of records; times are in nanoseconds, missing values are `null`.
+pub fn to_json(records: &[Record]) -> String {
+    let mut json = String::from("[");
+
+    for (i, record) in records.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write!(
+            json,
+            "\n  {{\"day\": {}, \"name\": {}, \"part\": {}, \"answer\": {}, \"parse_ns\": {}, \"solve_ns\": {}, \"error\": {}}}",
+            record.day.number,
+            json_string(Some(record.day.name)),
+            record.part.number(),
+            json_string(record.answer),
+            json_nanos(record.parse_time),
+            json_nanos(record.solve_time),
+            json_string(record.error),
+        )
+        .unwrap();
+    }
+    if !records.is_empty() {
+        json.push('\n');
+    }
+    json.push(']');
+
+    json
+}
+
+fn json_string(value: Option<&str>) -> String {
+    let Some(value) = value else {
+        return "null".to_string();
+    };
+
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+fn json_nanos(duration: Option<Duration>) -> String {
+    duration.map_or("null".to_string(), |duration| {
+        duration.as_nanos().to_string()
+    })
+}
+
+/// RFC 4180 CSV with a header row; times are in nanoseconds, missing values are empty.
+pub fn to_csv(records: &[Record]) -> String {
+    let mut csv = String::from("day,name,part,answer,parse_ns,solve_ns,error\r\n");
+
+    for record in records {
+        write!(
+            csv,
+            "{},{},{},{},{},{},{}\r\n",
+            record.day.number,
+            csv_field(record.day.name),
+            record.part.number(),
+            csv_field(record.answer.unwrap_or_default()),
+            csv_nanos(record.parse_time),
+            csv_nanos(record.solve_time),
+            csv_field(record.error.unwrap_or_default()),
+        )
+        .unwrap();
+    }
+
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn render(records: &[Record], format: Format) -> String {
+    match format {
+        Format::Json => to_json(records) + "\n",
+        Format::Csv => to_csv(records),
+    }
+}
+
+fn csv_nanos(duration: Option<Duration>) -> String {
+    duration.map_or(String::new(), |duration| duration.as_nanos().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Timings;
+    use advent_of_code_2022::registry::{self, Answers};
+
+    const CRT: &str = "@@..\n.@\"q\"";
+
+    fn results() -> Vec<(&'static Day, Outcome)> {
+        vec![
+            (
+                registry::find("10").unwrap(),
+                Outcome::Solved(Profile {
+                    answers: Answers {
+                        first: Some("a,b".to_string()),
+                        second: Some(CRT.to_string()),
+                    },
+                    timings: Timings {
+                        parse: Duration::from_nanos(1),
+                        first: Duration::from_nanos(2),
+                        second: Duration::from_nanos(3),
+                    },
+                    memory: None,
+                }),
+            ),
+            (
+                registry::find("11").unwrap(),
+                Outcome::Failed("bad \"input\",\tline 2".to_string()),
+            ),
+        ]
+    }
+
+    #[test]
+    fn escape_json_strings() {
+        assert_eq!(json_string(None), "null");
+        assert_eq!(json_string(Some(CRT)), r#""@@..\n.@\"q\"""#);
+        assert_eq!(
+            json_string(Some("\\ \r\t\u{1}\u{7f}é")),
+            r#""\\ \r\t\u0001\u007fé""#
+        );
+    }
+
+    #[test]
+    fn escape_csv_fields() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field(CRT), "\"@@..\n.@\"\"q\"\"\"");
+        assert_eq!(csv_field("a\rb"), "\"a\rb\"");
+    }
+
+    #[test]
+    fn render_solved_and_failed_days() {
+        let results = results();
+        let records = records(&results, None);
+        assert_eq!(records.len(), 4);
+
+        let json = to_json(&records);
+        assert!(json.contains(
+            r#"{"day": 10, "name": "cathode_ray_tube", "part": 2, "answer": "@@..\n.@\"q\"", "parse_ns": 1, "solve_ns": 3, "error": null}"#
+        ));
+        assert!(json.contains(
+            r#"{"day": 11, "name": "monkey_in_the_middle", "part": 1, "answer": null, "parse_ns": null, "solve_ns": null, "error": "bad \"input\",\tline 2"}"#
+        ));
+
+        let csv = to_csv(&records);
+        let rows = csv.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(rows[0], "day,name,part,answer,parse_ns,solve_ns,error");
+        assert_eq!(rows[1], "10,cathode_ray_tube,1,\"a,b\",1,2,");
+        assert_eq!(rows[2], "10,cathode_ray_tube,2,\"@@..\n.@\"\"q\"\"\",1,3,");
+        assert_eq!(
+            rows[3],
+            "11,monkey_in_the_middle,1,,,,\"bad \"\"input\"\",\tline 2\""
+        );
+        assert_eq!(rows[5], "");
+
+        assert_eq!(to_json(&[]), "[]");
+        assert_eq!(super::records(&results, Some(Part::Second)).len(), 2);
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Puzzle constants a day is solved with, such as the number of rounds.
+///
+/// `Default` gives the values from the puzzle text.
+pub trait Params: Default {
+    /// Names accepted by [`Params::set`].
+    const NAMES: &'static [&'static str];
+
+    /// Overrides the parameter `name` with the textual `value`.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String>;
+
+    fn unknown(name: &str) -> String {
+        if Self::NAMES.is_empty() {
+            format!("unknown parameter '{}', this day takes none", name)
+        } else {
+            format!(
+                "unknown parameter '{}', expected one of: {}",
+                name,
+                Self::NAMES.join(", ")
+            )
+        }
+    }
+}
+
+impl Params for () {
+    const NAMES: &'static [&'static str] = &[];
+
+    fn set(&mut self, name: &str, _value: &str) -> Result<(), String> {
+        Err(Self::unknown(name))
+    }
+}
+
+/// Parses the value of the parameter `name`.
+pub fn parse_value<T>(name: &str, value: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|err| format!("invalid value '{}' for {}: {}", value, name, err))
+}
+
+/// `name=value` overrides of a day's parameters, in the order given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides(Vec<(String, String)>);
+
+impl Overrides {
+    pub fn push(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.push((name.into(), value.into()));
+    }
+
+    /// Replaces the override of `name`, or adds one.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.0.iter_mut().find(|(current, _)| *current == name) {
+            Some((_, current)) => *current = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Default parameters with every override applied.
+    pub fn apply<P: Params>(&self) -> Result<P, String> {
+        let mut params = P::default();
+        for (name, value) in &self.0 {
+            params.set(name, value)?;
+        }
+
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rope_bridge::RopeParams;
+
+    fn overrides(pairs: &[(&str, &str)]) -> Overrides {
+        let mut overrides = Overrides::default();
+        for (name, value) in pairs {
+            overrides.push(*name, *value);
+        }
+
+        overrides
+    }
+
+    #[test]
+    fn apply_overrides_in_order() {
+        let params = overrides(&[("knots", "4"), ("knots", "5")])
+            .apply::<RopeParams>()
+            .unwrap();
+        assert_eq!(params.knots, 5);
+        assert_eq!(
+            Overrides::default().apply::<RopeParams>().unwrap().knots,
+            10
+        );
+    }
+
+    #[test]
+    fn reject_bad_overrides() {
+        assert_eq!(
+            overrides(&[("knot", "4")])
+                .apply::<RopeParams>()
+                .unwrap_err(),
+            "unknown parameter 'knot', expected one of: knots"
+        );
+        assert_eq!(
+            overrides(&[("knots", "many")])
+                .apply::<RopeParams>()
+                .unwrap_err(),
+            "invalid value 'many' for knots: invalid digit found in string"
+        );
+        assert!(overrides(&[("knots", "1")]).apply::<RopeParams>().is_err());
+        assert_eq!(
+            overrides(&[("x", "1")]).apply::<()>().unwrap_err(),
+            "unknown parameter 'x', this day takes none"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use std::fmt::{self, Display};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Error produced when a puzzle input cannot be parsed.
+///
+/// `line` and `column` are 1-based; `0` means the error is not tied to a
+/// position (for example an I/O failure or an unexpected end of input).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, text: &str, message: impl Into<String>) -> Self {
+        Self {
+            file: None,
+            line,
+            column,
+            text: text.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Error about the input as a whole, e.g. when it ends too early.
+    pub fn input(message: impl Into<String>) -> Self {
+        Self::new(0, 0, "", message)
+    }
+
+    pub fn io(err: std::io::Error) -> Self {
+        Self::input(err.to_string())
+    }
+
+    pub fn with_file(mut self, file: impl AsRef<Path>) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}", file.display())?,
+            None => write!(f, "<input>")?,
+        }
+        if self.line > 0 {
+            write!(f, ":{}:{}", self.line, self.column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if !self.text.is_empty() {
+            write!(f, "\n    {}", self.text)?;
+            if self.column > 0 {
+                write!(f, "\n    {:>width$}", "^", width = self.column)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A single input line together with its 1-based line number.
+///
+/// All the helpers report errors pointing at the offending token.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(number: usize, text: &'a str) -> Self {
+        Self { number, text }
+    }
+
+    /// Error about the whole line.
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.number, 1, self.text, message)
+    }
+
+    /// Error about `token`, which should be a slice of this line.
+    pub fn error_at(&self, token: &str, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.number, self.column_of(token), self.text, message)
+    }
+
+    pub fn parse<T>(&self, token: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        token
+            .parse()
+            .map_err(|err| self.error_at(token, format!("invalid value '{}': {}", token, err)))
+    }
+
+    pub fn split_once(
+        &self,
+        haystack: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), ParseError> {
+        haystack
+            .split_once(delimiter)
+            .ok_or_else(|| self.error_at(haystack, format!("expected '{}'", delimiter)))
+    }
+
+    /// Matches `text` against `pattern`, whose literal parts have to appear as they are and
+    /// whose `N` `{}` placeholders capture what lies between them.
+    ///
+    /// `line.fields(line.text, "move {} from {} to {}")` gives the three numbers of
+    /// `move 1 from 2 to 3`. A placeholder stops at the first occurrence of the literal
+    /// following it, or at the end of `text` for the last one.
+    pub fn fields<const N: usize>(
+        &self,
+        text: &'a str,
+        pattern: &str,
+    ) -> Result<[&'a str; N], ParseError> {
+        let mut literals = pattern.split("{}");
+        let mut rest = text;
+        let mut fields = Vec::with_capacity(N);
+
+        let first = literals.next().unwrap_or_default();
+        rest = rest
+            .strip_prefix(first)
+            .ok_or_else(|| self.error_at(rest, format!("expected '{}'", first)))?;
+        for literal in literals {
+            let end = match literal {
+                "" => rest.len(),
+                _ => rest
+                    .find(literal)
+                    .ok_or_else(|| self.error_at(rest, format!("expected '{}'", literal)))?,
+            };
+            fields.push(&rest[..end]);
+            rest = &rest[end + literal.len()..];
+        }
+        if !rest.is_empty() {
+            return Err(self.error_at(rest, format!("unexpected '{}'", rest)));
+        }
+
+        Ok(fields.try_into().unwrap_or_else(|fields: Vec<_>| {
+            panic!(
+                "pattern '{}' has {} fields, not {}",
+                pattern,
+                fields.len(),
+                N
+            )
+        }))
+    }
+
+    /// Splits a `key: value` line, trimming both sides.
+    pub fn key_value(&self) -> Result<(&'a str, &'a str), ParseError> {
+        let (key, value) = self.split_once(self.text, ":")?;
+
+        Ok((key.trim(), value.trim()))
+    }
+
+    /// Value of a `key: value` line, which has to be about `key`.
+    pub fn value(&self, key: &str) -> Result<&'a str, ParseError> {
+        match self.key_value() {
+            Ok((found, value)) if found == key => Ok(value),
+            Ok((found, _)) => Err(self.error_at(found, format!("expected '{}:'", key))),
+            Err(_) => Err(self.error(format!("expected '{}:'", key))),
+        }
+    }
+
+    /// Every integer in `text`, ignoring whatever separates them.
+    pub fn integers<T>(&self, text: &'a str) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let mut rest = text;
+        let mut integers = vec![];
+
+        while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+            // A minus sign directly in front belongs to the integer
+            let start = if rest[..start].ends_with('-') {
+                start - 1
+            } else {
+                start
+            };
+            let end = rest[start + 1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(rest.len(), |end| start + 1 + end);
+            integers.push(self.parse(&rest[start..end])?);
+            rest = &rest[end..];
+        }
+
+        Ok(integers)
+    }
+
+    fn column_of(&self, token: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let token_start = token.as_ptr() as usize;
+        let offset = if (start..=start + self.text.len()).contains(&token_start) {
+            Some(token_start - start)
+        } else {
+            self.text.find(token)
+        };
+
+        offset
+            .map(|offset| self.text[..offset].chars().count() + 1)
+            .unwrap_or(1)
+    }
+}
+
+pub fn read_lines(reader: impl BufRead) -> Result<Vec<String>, ParseError> {
+    reader
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(ParseError::io)
+}
+
+/// Calls `f` with every line of `reader` in turn.
+///
+/// Unlike [`read_lines`] only one line is held at a time, so inputs of any size can be
+/// parsed in constant memory.
+pub fn for_each_line(
+    mut reader: impl BufRead,
+    mut f: impl FnMut(Line) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    let mut buffer = String::new();
+    let mut number = 0;
+
+    loop {
+        buffer.clear();
+        if reader.read_line(&mut buffer).map_err(ParseError::io)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+
+        let text = buffer.strip_suffix('\n').unwrap_or(&buffer);
+        f(Line::new(number, text.strip_suffix('\r').unwrap_or(text)))?;
+    }
+}
+
+/// Calls `f` with the lines of every section of `reader`, sections being separated by
+/// blank lines.
+///
+/// Like [`for_each_line`] it holds a single section at a time.
+pub fn for_each_section(
+    reader: impl BufRead,
+    mut f: impl FnMut(&[Line]) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    let mut section = vec![];
+    let mut flush = |section: &mut Vec<(usize, String)>| {
+        if section.is_empty() {
+            return Ok(());
+        }
+        let lines = section
+            .iter()
+            .map(|(number, text)| Line::new(*number, text))
+            .collect::<Vec<_>>();
+        f(&lines)?;
+        section.clear();
+        Ok(())
+    };
+
+    for_each_line(reader, |line| {
+        if line.text.is_empty() {
+            flush(&mut section)
+        } else {
+            section.push((line.number, line.text.to_string()));
+            Ok(())
+        }
+    })?;
+    flush(&mut section)
+}
+
+/// Splits lines into their non-empty sections separated by blank lines.
+pub fn sections<'a, 'b>(lines: &'b [Line<'a>]) -> impl Iterator<Item = &'b [Line<'a>]> {
+    lines
+        .split(|line| line.text.is_empty())
+        .filter(|section| !section.is_empty())
+}
+
+pub fn numbered(lines: &[String]) -> impl Iterator<Item = Line<'_>> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| Line::new(i + 1, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_lines() {
+        let mut lines = vec![];
+        for_each_line("a\r\n\nb".as_bytes(), |line| {
+            lines.push((line.number, line.text.to_string()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            lines,
+            [
+                (1, "a".to_string()),
+                (2, String::new()),
+                (3, "b".to_string())
+            ]
+        );
+
+        let err = for_each_line("1\nx".as_bytes(), |line| {
+            line.parse::<u8>(line.text).map(|_| ())
+        })
+        .unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+
+    #[test]
+    fn split_sections() {
+        let mut streamed = vec![];
+        for_each_section("a\nb\n\n\nc\n".as_bytes(), |lines| {
+            streamed.push(lines.iter().map(|line| line.number).collect::<Vec<_>>());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(streamed, [vec![1, 2], vec![5]]);
+
+        let lines = ["", "a", "", "b", "c"].map(String::from);
+        let lines = numbered(&lines).collect::<Vec<_>>();
+        let lengths = sections(&lines).map(<[Line]>::len).collect::<Vec<_>>();
+        assert_eq!(lengths, [1, 2]);
+    }
+
+    #[test]
+    fn match_fields() {
+        let line = Line::new(1, "move 1 from 2 to 3");
+        assert_eq!(
+            line.fields(line.text, "move {} from {} to {}"),
+            Ok(["1", "2", "3"])
+        );
+
+        let err = line.fields::<2>(line.text, "move {} onto {}").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (6, "expected ' onto '"));
+        let err = line.fields::<1>(line.text, "move {} from 2").unwrap_err();
+        assert_eq!(
+            (err.column, err.message.as_str()),
+            (14, "unexpected ' to 3'")
+        );
+        let err = line.fields::<0>(line.text, "copy").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (1, "expected 'copy'"));
+    }
+
+    #[test]
+    fn key_value_lines() {
+        let line = Line::new(1, "  Starting items: 79, -98");
+        assert_eq!(line.key_value(), Ok(("Starting items", "79, -98")));
+        assert_eq!(line.value("Starting items"), Ok("79, -98"));
+        assert_eq!(line.value("Operation").unwrap_err().column, 3);
+        assert_eq!(line.integers::<i64>(line.text), Ok(vec![79, -98]));
+
+        let err = line.integers::<u8>(line.text).unwrap_err();
+        assert_eq!(err.column, 23);
+    }
+}
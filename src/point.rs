@@ -0,0 +1,196 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// Integer position or offset on a plane.
+///
+/// `y` grows downwards, matching [`Grid`](crate::grid::Grid) rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point::new(0, 0);
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    /// Component-wise sign, i.e. a step of at most one towards the offset.
+    pub fn signum(self) -> Self {
+        Self::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn manhattan(self, other: Point) -> u64 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Distance allowing diagonal moves, as a king moves on a chessboard.
+    pub fn chebyshev(self, other: Point) -> u64 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// Orthogonal neighbours, clockwise from up.
+    pub fn neighbours4(self) -> impl Iterator<Item = Point> {
+        Direction::ALL
+            .into_iter()
+            .map(move |direction| self + direction.offset())
+    }
+
+    /// Orthogonal and diagonal neighbours, clockwise from up.
+    pub fn neighbours8(self) -> impl Iterator<Item = Point> {
+        [
+            Point::new(0, -1),
+            Point::new(1, -1),
+            Point::new(1, 0),
+            Point::new(1, 1),
+            Point::new(0, 1),
+            Point::new(-1, 1),
+            Point::new(-1, 0),
+            Point::new(-1, -1),
+        ]
+        .into_iter()
+        .map(move |offset| self + offset)
+    }
+}
+
+/// Grid positions, as `(x, y)` pairs.
+impl From<(usize, usize)> for Point {
+    fn from((x, y): (usize, usize)) -> Self {
+        Self::new(x as i64, y as i64)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Point) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, rhs: i64) -> Point {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// Unit step in this direction.
+    pub fn offset(self) -> Point {
+        match self {
+            Direction::Up => Point::new(0, -1),
+            Direction::Right => Point::new(1, 0),
+            Direction::Down => Point::new(0, 1),
+            Direction::Left => Point::new(-1, 0),
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn turn_left(self) -> Direction {
+        self.turn_right().opposite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let mut point = Point::new(2, -3);
+        point += Point::new(1, 1);
+
+        assert_eq!(point, Point::new(3, -2));
+        assert_eq!(point - Point::new(3, 0), Point::new(0, -2));
+        assert_eq!(point * 2, Point::new(6, -4));
+        assert_eq!(-point, Point::new(-3, 2));
+        assert_eq!(Point::new(-7, 0).signum(), Point::new(-1, 0));
+    }
+
+    #[test]
+    fn distances() {
+        let (a, b) = (Point::new(1, 1), Point::new(4, -1));
+
+        assert_eq!(a.manhattan(b), 5);
+        assert_eq!(a.chebyshev(b), 3);
+        assert_eq!(b.chebyshev(b), 0);
+    }
+
+    #[test]
+    fn directions() {
+        for direction in Direction::ALL {
+            assert_eq!(
+                direction.offset() + direction.opposite().offset(),
+                Point::ORIGIN
+            );
+            assert_eq!(direction.turn_left().turn_right(), direction);
+        }
+        assert_eq!(Point::ORIGIN.neighbours4().count(), 4);
+        assert!(Point::ORIGIN
+            .neighbours8()
+            .all(|point| point.chebyshev(Point::ORIGIN) == 1));
+    }
+}
@@ -1,11 +1,58 @@
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+use crate::params::Params;
+use crate::parse::ParseError;
+
+/// Answers of both parts of a day, `None` for a part that was not run or has no answer.
+pub type Solution<T> = (
+    Option<<T as Problem>::OutputDataFirstPart>,
+    Option<<T as Problem>::OutputDataSecondPart>,
+);
+
 pub trait Problem {
-    type InputData: Clone;
+    type InputData;
     type OutputDataFirstPart;
     type OutputDataSecondPart;
+    /// Puzzle constants, `()` for days without any.
+    type Params: Params;
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError>;
+    /// `None` when the input has no answer with these parameters, as for the second part.
+    fn first_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart>;
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart>;
+
+    /// Replays the day's simulation as text frames, passing each one to `frame` as soon as it
+    /// is drawn. Returns `false` for days without anything to show.
+    fn visualize(
+        _input: &Self::InputData,
+        _params: &Self::Params,
+        _frame: &mut dyn FnMut(String),
+    ) -> bool {
+        false
+    }
+
+    /// Dump of the parsed input for debugging, such as a directory tree or stack contents;
+    /// `None` for days without one.
+    fn describe(_input: &Self::InputData, _params: &Self::Params) -> Option<String> {
+        None
+    }
+
+    fn parse_str(input: &str) -> Result<Self::InputData, ParseError> {
+        Self::parse(input.as_bytes())
+    }
+
+    fn read_file(filename: impl AsRef<Path>) -> Result<Self::InputData, ParseError> {
+        let filename = filename.as_ref();
+        let file =
+            std::fs::File::open(filename).map_err(|err| ParseError::io(err).with_file(filename))?;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData;
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart;
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart>;
+        Self::parse(BufReader::new(file)).map_err(|err| err.with_file(filename))
+    }
 }
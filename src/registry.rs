@@ -0,0 +1,202 @@
+use std::fmt;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::calorie_counting::CalorieCounting;
+use crate::camp_cleanup::CampCleanup;
+use crate::cathode_ray_tube::CathodeRayTube;
+use crate::distress_signal::DistressSignal;
+use crate::dyn_problem::DynProblem;
+use crate::generate::DynGenerate;
+use crate::hill_climbing_algorithm::HillClimbingAlgorith;
+use crate::monkey_in_the_middle::MonkeyInTheMiddle;
+use crate::no_space_left_on_device::NoSpaceLeftOnDevice;
+use crate::params::Overrides;
+use crate::parse::ParseError;
+use crate::regolith_reservoir::RegolithReservoir;
+use crate::rock_paper_scissors::RockPaperScissors;
+use crate::rope_bridge::RopeBridge;
+use crate::rucksack_reorganization::RucksackReorganization;
+use crate::single_pass::DynSinglePass;
+use crate::supply_stacks::SupplyStacks;
+use crate::treetop_tree_house::TreetopTreeHouse;
+use crate::tuning_trouble::TuningTrouble;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Part {
+    First,
+    Second,
+}
+
+impl Part {
+    pub const ALL: [Part; 2] = [Part::First, Part::Second];
+
+    pub fn number(self) -> u8 {
+        match self {
+            Part::First => 1,
+            Part::Second => 2,
+        }
+    }
+
+    pub fn from_number(number: u8) -> Option<Part> {
+        match number {
+            1 => Some(Part::First),
+            2 => Some(Part::Second),
+            _ => None,
+        }
+    }
+
+    /// Whether this part runs when only `selected` is asked for, `None` meaning both parts.
+    pub fn is_selected(self, selected: Option<Part>) -> bool {
+        selected.is_none_or(|selected| selected == self)
+    }
+}
+
+/// Answers of a single day rendered with `Display`, `None` for a part that was not run or
+/// has no answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answers {
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+impl Answers {
+    pub fn get(&self, part: Part) -> Option<&String> {
+        match part {
+            Part::First => self.first.as_ref(),
+            Part::Second => self.second.as_ref(),
+        }
+    }
+}
+
+pub struct Day {
+    pub number: u8,
+    pub name: &'static str,
+    pub problem: &'static dyn DynProblem,
+    /// Streaming solver of days implementing [`SinglePass`](crate::single_pass::SinglePass).
+    pub single_pass: Option<&'static dyn DynSinglePass>,
+    /// Input generator of days implementing [`Generate`](crate::generate::Generate).
+    pub generator: Option<&'static dyn DynGenerate>,
+}
+
+impl Day {
+    const fn new(number: u8, name: &'static str, problem: &'static dyn DynProblem) -> Self {
+        Self {
+            number,
+            name,
+            problem,
+            single_pass: None,
+            generator: None,
+        }
+    }
+
+    const fn with_generator(self, generator: &'static dyn DynGenerate) -> Self {
+        Self {
+            generator: Some(generator),
+            ..self
+        }
+    }
+
+    const fn with_single_pass(self, solver: &'static dyn DynSinglePass) -> Self {
+        Self {
+            single_pass: Some(solver),
+            ..self
+        }
+    }
+
+    /// Parses `filename` and solves the `part` asked for, both when `None`.
+    pub fn solve(
+        &self,
+        filename: &Path,
+        overrides: &Overrides,
+        part: Option<Part>,
+    ) -> Result<Answers, ParseError> {
+        let params = self.problem.params(overrides).map_err(ParseError::input)?;
+        let input = self.problem.read_file(filename)?;
+
+        Ok(Answers {
+            first: Part::First
+                .is_selected(part)
+                .then(|| self.problem.first_part(&*input, &*params))
+                .flatten()
+                .map(|answer| answer.to_string()),
+            second: Part::Second
+                .is_selected(part)
+                .then(|| self.problem.second_part(&*input, &*params))
+                .flatten()
+                .map(|answer| answer.to_string()),
+        })
+    }
+
+    /// Solves the day while streaming its input, see [`SinglePass`].
+    pub fn solve_single_pass(
+        &self,
+        filename: &Path,
+        overrides: &Overrides,
+        part: Option<Part>,
+    ) -> Result<Answers, ParseError> {
+        let solver = self.single_pass.ok_or_else(|| {
+            ParseError::input(format!("Day {} has no single-pass solver", self.number))
+        })?;
+        let params = self.problem.params(overrides).map_err(ParseError::input)?;
+        let file =
+            std::fs::File::open(filename).map_err(|err| ParseError::io(err).with_file(filename))?;
+        let (first, second) = solver
+            .solve(&mut BufReader::new(file), &*params, part)
+            .map_err(|err| err.with_file(filename))?;
+
+        Ok(Answers {
+            first: first.map(|answer| answer.to_string()),
+            second: second.map(|answer| answer.to_string()),
+        })
+    }
+
+    /// Checks that the overrides name parameters of this day with valid values.
+    pub fn check_params(&self, overrides: &Overrides) -> Result<(), String> {
+        self.problem.params(overrides).map(|_| ())
+    }
+}
+
+impl fmt::Debug for Day {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Day")
+            .field("number", &self.number)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+pub const DAYS: [Day; 14] = [
+    Day::new(1, "calorie_counting", &CalorieCounting)
+        .with_generator(&CalorieCounting)
+        .with_single_pass(&CalorieCounting),
+    Day::new(2, "rock_paper_scissors", &RockPaperScissors).with_generator(&RockPaperScissors),
+    Day::new(3, "rucksack_reorganization", &RucksackReorganization)
+        .with_generator(&RucksackReorganization)
+        .with_single_pass(&RucksackReorganization),
+    Day::new(4, "camp_cleanup", &CampCleanup)
+        .with_generator(&CampCleanup)
+        .with_single_pass(&CampCleanup),
+    Day::new(5, "supply_stacks", &SupplyStacks).with_generator(&SupplyStacks),
+    Day::new(6, "tuning_trouble", &TuningTrouble).with_generator(&TuningTrouble),
+    Day::new(7, "no_space_left_on_device", &NoSpaceLeftOnDevice)
+        .with_generator(&NoSpaceLeftOnDevice),
+    Day::new(8, "treetop_tree_house", &TreetopTreeHouse).with_generator(&TreetopTreeHouse),
+    Day::new(9, "rope_bridge", &RopeBridge).with_generator(&RopeBridge),
+    Day::new(10, "cathode_ray_tube", &CathodeRayTube).with_generator(&CathodeRayTube),
+    Day::new(11, "monkey_in_the_middle", &MonkeyInTheMiddle).with_generator(&MonkeyInTheMiddle),
+    Day::new(12, "hill_climbing_algorithm", &HillClimbingAlgorith)
+        .with_generator(&HillClimbingAlgorith),
+    Day::new(13, "distress_signal", &DistressSignal).with_generator(&DistressSignal),
+    Day::new(14, "regolith_reservoir", &RegolithReservoir).with_generator(&RegolithReservoir),
+];
+
+/// Looks a day up by its number (`14`) or name (`regolith_reservoir` or `regolith-reservoir`).
+pub fn find(query: &str) -> Option<&'static Day> {
+    if let Ok(number) = query.parse::<u8>() {
+        return DAYS.iter().find(|day| day.number == number);
+    }
+
+    let name = query.replace('-', "_");
+    DAYS.iter().find(|day| day.name == name)
+}
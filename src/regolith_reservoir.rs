@@ -1,103 +1,409 @@
+use crate::cancel;
+use crate::generate::{Generate, Rng};
+use crate::grid::Grid;
+use crate::params::{self, Params};
+use crate::parse::{self, ParseError};
+use crate::point::Point;
 use crate::problem::Problem;
-use std::collections::HashSet;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
-pub struct RegolithReservoir;
+/// Where sand tries to go, in order of preference.
+const FALLS: [Point; 3] = [Point::new(0, 1), Point::new(-1, 1), Point::new(1, 1)];
 
-impl Problem for RegolithReservoir {
-    type InputData = HashSet<(usize, usize)>;
-    type OutputDataFirstPart = u64;
-    type OutputDataSecondPart = u64;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Air,
+    Rock,
+    Sand,
+}
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .fold(HashSet::new(), |mut rock_positions, line| {
-                let mut positions = line.split(" -> ").map(|pos| {
-                    let (l, r) = pos.split_once(',').unwrap();
-                    (l.parse::<usize>().unwrap(), r.parse::<usize>().unwrap())
-                });
-                let mut current = positions.next().unwrap();
-                for (x, y) in positions {
-                    if x == current.0 {
-                        rock_positions
-                            .extend((y.min(current.1)..=y.max(current.1)).map(|y| (x, y)));
-                    } else if y == current.1 {
-                        rock_positions
-                            .extend((x.min(current.0)..=x.max(current.0)).map(|x| (x, y)));
-                    }
-                    current = (x, y);
-                }
+#[derive(Debug, Clone)]
+pub struct SandParams {
+    pub source_x: i64,
+    pub source_y: i64,
+}
 
-                rock_positions
-            })
+impl Default for SandParams {
+    fn default() -> Self {
+        Self {
+            source_x: 500,
+            source_y: 0,
+        }
     }
+}
+
+impl Params for SandParams {
+    const NAMES: &'static [&'static str] = &["source_x", "source_y"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "source_x" => self.source_x = params::parse_value(name, value)?,
+            "source_y" => match params::parse_value(name, value)? {
+                y @ 0.. => self.source_y = y,
+                _ => return Err("source_y should not be negative".to_string()),
+            },
+            _ => return Err(Self::unknown(name)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Every rock tile of the scan, in puzzle coordinates.
+#[derive(Debug, Clone)]
+pub struct Scan {
+    rocks: Vec<Point>,
+    bottom_limit: i64,
+}
+
+/// Slice of the cave wide enough for sand from `source` to pile up on the floor.
+///
+/// Column `0` of the grid is `left` in puzzle coordinates and the last row is the floor.
+#[derive(Debug, Clone)]
+struct Cave {
+    grid: Grid<Tile>,
+    left: i64,
+    source: Point,
+}
+
+impl Cave {
+    /// `None` when the source is not above the floor, leaving no room for sand.
+    fn new(scan: &Scan, params: &SandParams) -> Option<Self> {
+        let floor = scan.bottom_limit + 2;
+        if params.source_y >= floor {
+            return None;
+        }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        const START: (usize, usize) = (500, 0);
-        let mut particles = input;
-        let bottom_limit = particles.iter().map(|(_, y)| *y).max().unwrap();
+        // Sand moves at most one column per row, so it never leaves the floor's span
+        let spread = floor - params.source_y + 1;
+        let rocks_x = scan.rocks.iter().map(|rock| rock.x);
+        let left = rocks_x
+            .clone()
+            .chain([params.source_x - spread])
+            .min()
+            .unwrap();
+        let right = rocks_x.chain([params.source_x + spread]).max().unwrap();
+
+        let mut grid = Grid::new((right - left + 1) as usize, (floor + 1) as usize, Tile::Air);
+        for &rock in &scan.rocks {
+            grid[rock - Point::new(left, 0)] = Tile::Rock;
+        }
+        for x in 0..grid.width() {
+            grid[(x, floor as usize)] = Tile::Rock;
+        }
+
+        Some(Self {
+            grid,
+            left,
+            source: Point::new(params.source_x - left, params.source_y),
+        })
+    }
+}
+
+pub struct RegolithReservoir;
+
+impl RegolithReservoir {
+    /// Pours sand until it flows into the abyss below `bottom_limit` or blocks the source,
+    /// calling `on_rest` every time a unit comes to rest, and returns the resting units.
+    fn pour_into_abyss(
+        cave: &mut Cave,
+        bottom_limit: i64,
+        mut on_rest: impl FnMut(&Grid<Tile>),
+    ) -> u64 {
+        let start = cave.source;
+        let particles = &mut cave.grid;
         let mut sand_count = 0;
 
         'generator: loop {
-            let mut sand_position = START;
+            cancel::checkpoint();
+            let mut sand_position = start;
             'moving: loop {
-                let (x, y) = sand_position;
-                if y > bottom_limit {
+                if sand_position.y > bottom_limit {
                     break 'generator sand_count;
                 }
 
-                if let Some(next_position) = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+                if let Some(next_position) = FALLS
                     .into_iter()
-                    .find(|point| !particles.contains(point))
+                    .map(|fall| sand_position + fall)
+                    .find(|&point| particles[point] == Tile::Air)
                 {
                     sand_position = next_position;
                     continue;
                 }
 
-                particles.insert(sand_position);
+                particles[sand_position] = Tile::Sand;
                 sand_count += 1;
+                on_rest(particles);
+                if sand_position == start {
+                    // Sand piled up to the source before any reached the abyss
+                    break 'generator sand_count;
+                }
                 break 'moving;
             }
         }
     }
+}
+
+impl Problem for RegolithReservoir {
+    type InputData = Scan;
+    type OutputDataFirstPart = u64;
+    type OutputDataSecondPart = u64;
+    type Params = SandParams;
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let lines = parse::read_lines(reader)?;
+        let rocks = parse::numbered(&lines).try_fold(vec![], |mut rock_positions, line| {
+            let mut positions = line.text.split(" -> ").map(|pos| {
+                let [x, y] = line.fields(pos, "{},{}")?;
+                Ok(Point::from((
+                    line.parse::<usize>(x)?,
+                    line.parse::<usize>(y)?,
+                )))
+            });
+            let mut current = positions
+                .next()
+                .unwrap_or_else(|| Err(line.error("expected rock path")))?;
+            rock_positions.push(current);
+            for position in positions {
+                let position = position?;
+                let step = (position - current).signum();
+                if step.x != 0 && step.y != 0 {
+                    return Err(line.error("rock path segments should be straight lines"));
+                }
+                while current != position {
+                    current += step;
+                    rock_positions.push(current);
+                }
+            }
+
+            Ok(rock_positions)
+        })?;
+
+        let Some(bottom_limit) = rocks.iter().map(|rock| rock.y).max() else {
+            return Err(ParseError::input("expected at least one rock path"));
+        };
+
+        Ok(Scan {
+            rocks,
+            bottom_limit,
+        })
+    }
+
+    fn first_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        let mut cave = Cave::new(input, params)?;
+        Some(RegolithReservoir::pour_into_abyss(
+            &mut cave,
+            input.bottom_limit,
+            |_| {},
+        ))
+    }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        const START: (usize, usize) = (500, 0);
-        let mut particles = input;
-        let floor = particles.iter().map(|(_, y)| *y).max().unwrap() + 2;
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        let Cave {
+            grid: mut particles,
+            source: start,
+            ..
+        } = Cave::new(input, params)?;
         let mut sand_count = 0;
 
         Some('generator: loop {
-            let mut sand_position = START;
+            cancel::checkpoint();
+            let mut sand_position = start;
             'moving: loop {
-                let (x, y) = sand_position;
-                if y == floor {
-                    particles.insert(sand_position);
-                    break 'moving;
-                }
-
-                if let Some(next_position) = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+                if let Some(next_position) = FALLS
                     .into_iter()
-                    .find(|point| !particles.contains(point))
+                    .map(|fall| sand_position + fall)
+                    .find(|&point| particles[point] == Tile::Air)
                 {
                     sand_position = next_position;
                     continue;
                 }
 
-                if sand_position == START {
-                    // + 1, cause (500, 0) also new sand particle
+                if sand_position == start {
+                    // + 1, cause the source also gets a sand particle
                     break 'generator sand_count + 1;
                 }
 
-                particles.insert(sand_position);
+                particles[sand_position] = Tile::Sand;
                 sand_count += 1;
                 break 'moving;
             }
         })
     }
+
+    /// The first part's cave each time a unit of sand comes to rest, cropped to the rocks:
+    /// `#` is rock, `o` sand and `+` the source.
+    fn visualize(
+        input: &Self::InputData,
+        params: &Self::Params,
+        frame: &mut dyn FnMut(String),
+    ) -> bool {
+        // Without room for sand there is nothing to show but no error either
+        let Some(mut cave) = Cave::new(input, params) else {
+            return true;
+        };
+        let source = cave.source;
+        let rocks_x = input.rocks.iter().map(|rock| rock.x - cave.left);
+        let left = rocks_x.clone().chain([source.x]).min().unwrap();
+        let right = rocks_x.chain([source.x]).max().unwrap();
+        let columns = (left - 1).max(0)..=(right + 1).min(cave.grid.width() as i64 - 1);
+
+        let mut draw = |particles: &Grid<Tile>| {
+            let lines = (0..=input.bottom_limit)
+                .map(|y| {
+                    columns
+                        .clone()
+                        .map(|x| match particles[Point::new(x, y)] {
+                            _ if Point::new(x, y) == source => '+',
+                            Tile::Air => '.',
+                            Tile::Rock => '#',
+                            Tile::Sand => 'o',
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+
+            frame(lines.join("\n"));
+        };
+
+        draw(&cave.grid);
+        RegolithReservoir::pour_into_abyss(&mut cave, input.bottom_limit, draw);
+
+        true
+    }
+}
+
+impl Generate for RegolithReservoir {
+    /// `size` rock paths of one to four segments around the sand source, at most about `size`
+    /// tiles deep.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| {
+                let depth = size as i64 + 5;
+                let mut rock = Point::new(rng.range(450..=550), rng.range(5..=depth));
+                let mut points = vec![rock];
+                let mut horizontal = rng.chance(1, 2);
+                for _ in 0..rng.range(1..=4) {
+                    let length = rng.range(-8..=8);
+                    if horizontal {
+                        rock.x += length;
+                    } else {
+                        rock.y = (rock.y + length).max(1);
+                    }
+                    points.push(rock);
+                    horizontal = !horizontal;
+                }
+
+                points
+                    .iter()
+                    .map(|point| format!("{},{}", point.x, point.y))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const EXAMPLE: &str = "\
+498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9";
+
+    #[test]
+    fn first_part_example() {
+        let input = RegolithReservoir::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RegolithReservoir::first_part(&input, &Default::default()),
+            Some(24)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = RegolithReservoir::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RegolithReservoir::second_part(&input, &Default::default()),
+            Some(93)
+        );
+    }
+
+    #[test]
+    fn source_on_the_floor() {
+        let input = RegolithReservoir::parse_str(EXAMPLE).unwrap();
+        let params = SandParams {
+            source_y: 11,
+            ..Default::default()
+        };
+
+        assert_eq!(RegolithReservoir::first_part(&input, &params), None);
+        assert_eq!(RegolithReservoir::second_part(&input, &params), None);
+        assert!(RegolithReservoir::visualize(&input, &params, &mut |_| {
+            panic!("no frame expected")
+        }));
+    }
+
+    /// Drops sand into a set of blocked tiles, one particle at a time.
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        let mut rocks = HashSet::new();
+        for line in input.lines() {
+            let points = line
+                .split(" -> ")
+                .map(|point| {
+                    let (x, y) = point.split_once(',').unwrap();
+                    (x.parse::<i64>().unwrap(), y.parse::<i64>().unwrap())
+                })
+                .collect::<Vec<_>>();
+            for pair in points.windows(2) {
+                let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                for x in x1.min(x2)..=x1.max(x2) {
+                    for y in y1.min(y2)..=y1.max(y2) {
+                        rocks.insert((x, y));
+                    }
+                }
+            }
+            rocks.insert(points[0]);
+        }
+        let bottom = rocks.iter().map(|&(_, y)| y).max().unwrap();
+
+        let pour = |floor: bool| {
+            let mut blocked = rocks.clone();
+            let mut resting = 0;
+            while !blocked.contains(&(500, 0)) {
+                let (mut x, mut y) = (500, 0);
+                loop {
+                    if !floor && y > bottom {
+                        return resting;
+                    }
+                    let next = [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)]
+                        .into_iter()
+                        .find(|&(nx, ny)| ny < bottom + 2 && !blocked.contains(&(nx, ny)));
+                    match next {
+                        Some(next) => (x, y) = next,
+                        None => break,
+                    }
+                }
+                blocked.insert((x, y));
+                resting += 1;
+            }
+            resting
+        };
+
+        (pour(false), Some(pour(true)))
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<RegolithReservoir>(15, reference);
+    }
 }
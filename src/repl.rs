@@ -0,0 +1,268 @@
+use std::any::Any;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use advent_of_code_2022::params::Overrides;
+use advent_of_code_2022::parse::ParseError;
+use advent_of_code_2022::registry::{Day, Part};
+
+use crate::runner;
+
+pub const HELP: &str = "\
+Commands:
+    1, 2                runs part one or two on the loaded input
+    run                 runs both parts
+    show                prints the parsed input, e.g. the directory tree or the stacks
+    set <name>=<value>  overrides a parameter for the next runs
+    params              lists the parameters of the day and their overrides
+    reset               drops every override
+    reload              parses the input file again
+    help                prints this help
+    quit                leaves";
+
+/// A day's input parsed once and kept for as many runs as needed.
+pub struct Session {
+    day: &'static Day,
+    input: PathBuf,
+    data: Box<dyn Any>,
+    overrides: Overrides,
+}
+
+impl Session {
+    pub fn load(day: &'static Day, input: &Path, overrides: Overrides) -> Result<Self, ParseError> {
+        day.check_params(&overrides).map_err(ParseError::input)?;
+
+        Ok(Self {
+            day,
+            input: input.to_path_buf(),
+            data: Self::parse(day, input)?,
+            overrides,
+        })
+    }
+
+    fn parse(day: &Day, input: &Path) -> Result<Box<dyn Any>, ParseError> {
+        let file =
+            std::fs::File::open(input).map_err(|err| ParseError::io(err).with_file(input))?;
+
+        day.problem
+            .parse(&mut BufReader::new(file))
+            .map_err(|err| err.with_file(input))
+    }
+
+    pub fn prompt(&self) -> String {
+        format!("day{:02}> ", self.day.number)
+    }
+
+    /// Runs a single command and returns what it printed, or `None` for `quit`.
+    pub fn execute(&mut self, command: &str) -> Option<String> {
+        let (command, argument) = command
+            .trim()
+            .split_once(' ')
+            .unwrap_or((command.trim(), ""));
+
+        let output = match (command, argument.trim()) {
+            ("", _) => String::new(),
+            ("1" | "2", "") => {
+                let part = Part::from_number(command.parse().unwrap()).unwrap();
+                self.run(part)
+            }
+            ("run", "") => format!("{}\n{}", self.run(Part::First), self.run(Part::Second)),
+            ("show", "") => {
+                let params = self.day.problem.params(&self.overrides).unwrap();
+                self.day
+                    .problem
+                    .describe(&*self.data, &*params)
+                    .unwrap_or_else(|| format!("Day {} has nothing to show", self.day.number))
+            }
+            ("set", argument) => match argument.split_once('=') {
+                Some((name, value)) => self.set(name.trim(), value.trim()),
+                None => "set expects <name>=<value>".to_string(),
+            },
+            ("params", "") => self.params(),
+            ("reset", "") => {
+                self.overrides = Overrides::default();
+                "Back to the puzzle's parameters".to_string()
+            }
+            ("reload", "") => match Self::parse(self.day, &self.input) {
+                Ok(data) => {
+                    self.data = data;
+                    format!("Reloaded {}", self.input.display())
+                }
+                Err(err) => format!("{}\nKept the previous input", err),
+            },
+            ("help", "") => HELP.to_string(),
+            ("quit" | "exit", "") => return None,
+            _ => format!("Unknown command '{}', try help", command.trim()),
+        };
+
+        Some(output)
+    }
+
+    fn run(&self, part: Part) -> String {
+        let params = self.day.problem.params(&self.overrides).unwrap();
+        let start = Instant::now();
+        let answer = runner::with_silent_panics(|| {
+            runner::isolate(|| {
+                Ok(match part {
+                    Part::First => self.day.problem.first_part(&*self.data, &*params),
+                    Part::Second => self.day.problem.second_part(&*self.data, &*params),
+                })
+            })
+        });
+        let time = runner::format_duration(start.elapsed());
+
+        match answer {
+            Ok(Some(answer)) if answer.to_string().contains('\n') => {
+                format!("Part {} ({}):\n{}", part.number(), time, answer)
+            }
+            Ok(Some(answer)) => format!("Part {}: {} ({})", part.number(), answer, time),
+            Ok(None) => format!("Part {}: -", part.number()),
+            Err(reason) => format!("Part {} failed: {}", part.number(), reason),
+        }
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> String {
+        let mut overrides = self.overrides.clone();
+        overrides.set(name, value);
+
+        match self.day.check_params(&overrides) {
+            Ok(()) => {
+                self.overrides = overrides;
+                format!("{} = {}", name, value)
+            }
+            Err(err) => err,
+        }
+    }
+
+    fn params(&self) -> String {
+        let names = self.day.problem.param_names();
+        if names.is_empty() {
+            return format!("Day {} takes no parameters", self.day.number);
+        }
+
+        names
+            .iter()
+            .map(|name| {
+                match self
+                    .overrides
+                    .iter()
+                    .find(|(overridden, _)| overridden == name)
+                {
+                    Some((_, value)) => format!("{} = {}", name, value),
+                    None => format!("{} (puzzle value)", name),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Reads commands from `input` until `quit` or the end of input, writing prompts and
+/// results to `output`.
+pub fn run(session: &mut Session, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "{}", session.prompt())?;
+        output.flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            return writeln!(output);
+        };
+        match session.execute(&line) {
+            Some(result) if result.is_empty() => {}
+            Some(result) => writeln!(output, "{}", result)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2022::registry;
+    use std::env;
+    use std::fs;
+
+    const SUPPLY_EXAMPLE: &str = "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    fn session(day: &str, input: &str) -> (Session, PathBuf) {
+        let day = registry::find(day).unwrap();
+        let path = env::temp_dir().join(format!(
+            "aoc-2022-repl-{}-{}.txt",
+            day.name,
+            std::process::id()
+        ));
+        fs::write(&path, input).unwrap();
+
+        (
+            Session::load(day, &path, Overrides::default()).unwrap(),
+            path,
+        )
+    }
+
+    #[test]
+    fn run_parts_and_show() {
+        let (mut session, path) = session("5", SUPPLY_EXAMPLE);
+
+        assert!(session.execute("1").unwrap().starts_with("Part 1: CMZ ("));
+        assert!(session.execute(" 2 ").unwrap().starts_with("Part 2: MCD ("));
+        assert_eq!(
+            session.execute("show").unwrap(),
+            "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n\n4 moves"
+        );
+        assert_eq!(
+            session.execute("params").unwrap(),
+            "Day 5 takes no parameters"
+        );
+        assert_eq!(
+            session.execute("frobnicate").unwrap(),
+            "Unknown command 'frobnicate', try help"
+        );
+        assert_eq!(session.execute("quit"), None);
+
+        fs::write(&path, " 1\n\nmove x from 1 to 1").unwrap();
+        assert!(session
+            .execute("reload")
+            .unwrap()
+            .ends_with("Kept the previous input"));
+        assert!(session.execute("1").unwrap().starts_with("Part 1: CMZ ("));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn change_params_without_reparsing() {
+        let (mut session, path) = session("1", "1000\n\n5000\n\n3000");
+
+        assert!(session.execute("2").unwrap().starts_with("Part 2: 9000 ("));
+        assert_eq!(session.execute("set top=1").unwrap(), "top = 1");
+        assert!(session.execute("2").unwrap().starts_with("Part 2: 5000 ("));
+        assert_eq!(session.execute("params").unwrap(), "top = 1");
+        assert!(session
+            .execute("set tops=2")
+            .unwrap()
+            .starts_with("unknown parameter 'tops'"));
+        assert_eq!(session.execute("params").unwrap(), "top = 1");
+
+        session.execute("reset");
+        assert_eq!(session.execute("params").unwrap(), "top (puzzle value)");
+
+        let mut output = vec![];
+        run(&mut session, "2\n\nquit\n1\n".as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("day01> Part 2: 9000 ("));
+        assert!(output.ends_with(")\nday01> day01> "));
+
+        fs::remove_file(path).unwrap();
+    }
+}
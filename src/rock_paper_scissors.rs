@@ -1,6 +1,7 @@
+use crate::generate::{Generate, Rng};
+use crate::parse::{self, ParseError};
 use crate::problem::Problem;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Choice {
@@ -10,15 +11,15 @@ pub enum Choice {
 }
 
 impl Choice {
-    fn translate(c: &str) -> Choice {
+    fn translate(c: &str) -> Option<Choice> {
         match c {
-            "A" => Choice::Rock,
-            "B" => Choice::Paper,
-            "C" => Choice::Scissors,
-            "X" => Choice::Rock,
-            "Y" => Choice::Paper,
-            "Z" => Choice::Scissors,
-            _ => panic!("UNKNOWN VARIANT"),
+            "A" => Some(Choice::Rock),
+            "B" => Some(Choice::Paper),
+            "C" => Some(Choice::Scissors),
+            "X" => Some(Choice::Rock),
+            "Y" => Some(Choice::Paper),
+            "Z" => Some(Choice::Scissors),
+            _ => None,
         }
     }
 
@@ -85,32 +86,112 @@ impl Problem for RockPaperScissors {
     type InputData = Vec<(Choice, Choice)>;
     type OutputDataFirstPart = u64;
     type OutputDataSecondPart = u64;
+    type Params = ();
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let lines = parse::read_lines(reader)?;
 
-        reader
-            .lines()
-            .map(Result::unwrap)
+        parse::numbered(&lines)
             .map(|line| {
-                let (l, r) = line.split_once(' ').unwrap();
-                (Choice::translate(l), Choice::translate(r))
+                let (l, r) = line.split_once(line.text, " ")?;
+                let translate = |c| {
+                    Choice::translate(c)
+                        .ok_or_else(|| line.error_at(c, format!("unknown variant '{}'", c)))
+                };
+
+                Ok((translate(l)?, translate(r)?))
             })
-            .collect()
+            .collect::<Result<_, ParseError>>()
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        input
-            .into_iter()
-            .fold(0, |acc, (l, r)| acc + score_first_part(l, r))
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        Some(
+            input
+                .iter()
+                .fold(0, |acc, &(l, r)| acc + score_first_part(l, r)),
+        )
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
         Some(
             input
-                .into_iter()
-                .fold(0, |acc, (l, r)| acc + score_second_part(l, r)),
+                .iter()
+                .fold(0, |acc, &(l, r)| acc + score_second_part(l, r)),
         )
     }
 }
+
+impl Generate for RockPaperScissors {
+    /// `size` rounds.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                format!(
+                    "{} {}",
+                    rng.pick(&["A", "B", "C"]),
+                    rng.pick(&["X", "Y", "Z"])
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+A Y
+B X
+C Z";
+
+    #[test]
+    fn first_part_example() {
+        let input = RockPaperScissors::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RockPaperScissors::first_part(&input, &Default::default()),
+            Some(15)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = RockPaperScissors::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RockPaperScissors::second_part(&input, &Default::default()),
+            Some(12)
+        );
+    }
+
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        // Score of every round for both readings of the second column
+        const ROUNDS: [(&str, u64, u64); 9] = [
+            ("A X", 1 + 3, 3),
+            ("A Y", 2 + 6, 1 + 3),
+            ("A Z", 3, 2 + 6),
+            ("B X", 1, 1),
+            ("B Y", 2 + 3, 2 + 3),
+            ("B Z", 3 + 6, 3 + 6),
+            ("C X", 1 + 6, 2),
+            ("C Y", 2, 3 + 3),
+            ("C Z", 3 + 3, 1 + 6),
+        ];
+
+        input.lines().fold((0, Some(0)), |(first, second), line| {
+            let (_, shape, outcome) = ROUNDS.iter().find(|(round, ..)| *round == line).unwrap();
+            (first + shape, second.map(|second| second + outcome))
+        })
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<RockPaperScissors>(200, reference);
+    }
+}
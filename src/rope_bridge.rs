@@ -1,112 +1,286 @@
 use std::collections::HashSet;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
+use crate::cancel;
+use crate::generate::{Generate, Rng};
+use crate::grid::Grid;
+use crate::params::{self, Params};
+use crate::parse::{self, ParseError};
+use crate::point::{Direction, Point};
 use crate::problem::Problem;
 
+#[derive(Debug, Clone)]
+pub struct RopeParams {
+    /// Knots of the rope in the second part, head and tail included.
+    pub knots: usize,
+}
+
+impl Default for RopeParams {
+    fn default() -> Self {
+        Self { knots: 10 }
+    }
+}
+
+impl Params for RopeParams {
+    const NAMES: &'static [&'static str] = &["knots"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "knots" => match params::parse_value(name, value)? {
+                knots @ 2.. => self.knots = knots,
+                _ => return Err("a rope needs at least 2 knots".to_string()),
+            },
+            _ => return Err(Self::unknown(name)),
+        }
+
+        Ok(())
+    }
+}
+
 pub struct RopeBridge;
 
 impl RopeBridge {
-    fn get_direction(sym: &str) -> (i64, i64) {
+    fn get_direction(sym: &str) -> Option<Direction> {
         match sym {
-            "U" => (0, 1),
-            "D" => (0, -1),
-            "R" => (1, 0),
-            "L" => (-1, 0),
-            _ => panic!("Unexpected direction"),
+            "U" => Some(Direction::Up),
+            "D" => Some(Direction::Down),
+            "R" => Some(Direction::Right),
+            "L" => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    /// Moves `knot` one step towards `leader` once they stop touching.
+    fn follow(knot: &mut Point, leader: Point) -> bool {
+        if knot.chebyshev(leader) < 2 {
+            return false;
         }
+
+        *knot += (leader - *knot).signum();
+        true
     }
 
-    fn get_distance(l: (i64, i64), r: (i64, i64)) -> (i64, i64) {
-        (r.0 - l.0, r.1 - l.1)
+    /// Pulls a rope of `knots` knots through the motions, calling `on_motion` before the
+    /// first one and after each, and returns the positions visited by the tail.
+    fn pull(
+        input: &[(Direction, u64)],
+        knots: usize,
+        mut on_motion: impl FnMut(&[Point], &HashSet<Point>),
+    ) -> HashSet<Point> {
+        const FIRST: usize = 0;
+        let last = knots - 1;
+
+        let mut unique_position = HashSet::new();
+        let mut rope = vec![Point::ORIGIN; knots];
+
+        unique_position.insert(rope[last]);
+        on_motion(&rope, &unique_position);
+        for &(direction, steps) in input {
+            for _ in 0..steps {
+                cancel::checkpoint();
+                rope[FIRST] += direction.offset();
+
+                for i in 1..knots {
+                    let leader = rope[i - 1];
+                    if RopeBridge::follow(&mut rope[i], leader) && i == last {
+                        unique_position.insert(rope[i]);
+                    }
+                }
+            }
+            on_motion(&rope, &unique_position);
+        }
+
+        unique_position
     }
 }
 
 impl Problem for RopeBridge {
-    type InputData = Vec<((i64, i64), u64)>;
+    type InputData = Vec<(Direction, u64)>;
     type OutputDataFirstPart = usize;
     type OutputDataSecondPart = usize;
+    type Params = RopeParams;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut motions = vec![];
+        parse::for_each_line(reader, |line| {
+            let (l, r) = line.split_once(line.text, " ")?;
+            let direction = RopeBridge::get_direction(l)
+                .ok_or_else(|| line.error_at(l, format!("unexpected direction '{}'", l)))?;
 
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .map(|line| {
-                let (l, r) = line.split_once(' ').unwrap();
-                (RopeBridge::get_direction(l), r.parse::<u64>().unwrap())
-            })
-            .collect()
+            motions.push((direction, line.parse::<u64>(r)?));
+            Ok(())
+        })?;
+
+        Ok(motions)
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
         let mut unique_position = HashSet::new();
-        let mut h = (1, 1);
-        let mut t = (1, 1);
+        let mut h = Point::ORIGIN;
+        let mut t = Point::ORIGIN;
 
         unique_position.insert(t);
-        for command in input {
-            for _ in 0..command.1 {
-                h.0 += command.0 .0;
-                h.1 += command.0 .1;
-                let (x, y) = RopeBridge::get_distance(t, h);
-
-                if x.abs() == 2 || y.abs() == 2 {
-                    t.0 += x.normalize();
-                    t.1 += y.normalize();
+        for &(direction, steps) in input {
+            for _ in 0..steps {
+                cancel::checkpoint();
+                h += direction.offset();
 
+                if RopeBridge::follow(&mut t, h) {
                     unique_position.insert(t);
                 }
             }
         }
 
-        unique_position.len()
+        Some(unique_position.len())
+    }
+
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        Some(RopeBridge::pull(input, params.knots, |_, _| {}).len())
+    }
+
+    /// The rope of the second part after every motion: `H` is the head, knots are numbered
+    /// from 1, `s` is the start and `#` the positions the tail visited.
+    fn visualize(
+        input: &Self::InputData,
+        params: &Self::Params,
+        frame: &mut dyn FnMut(String),
+    ) -> bool {
+        // The knots never leave the area the head went through
+        let mut head = Point::ORIGIN;
+        let (mut min, mut max) = (head, head);
+        for &(direction, steps) in input {
+            head += direction.offset() * steps as i64;
+            min = Point::new(min.x.min(head.x), min.y.min(head.y));
+            max = Point::new(max.x.max(head.x), max.y.max(head.y));
+        }
+
+        let size = max - min + Point::new(1, 1);
+        let empty = Grid::new(size.x as usize, size.y as usize, '.');
+        RopeBridge::pull(input, params.knots, |rope, visited| {
+            let mut area = empty.clone();
+            for &position in visited {
+                area[position - min] = '#';
+            }
+            area[Point::ORIGIN - min] = 's';
+            for (i, &knot) in rope.iter().enumerate().rev() {
+                area[knot - min] = match i {
+                    0 => 'H',
+                    _ => char::from_digit(i as u32 % 10, 10).unwrap(),
+                };
+            }
+
+            frame(area.render(|&c| c));
+        });
+
+        true
     }
+}
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        const LAST: usize = 9;
-        const FIRST: usize = 0;
+impl Generate for RopeBridge {
+    /// `size` head motions.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| format!("{} {}", rng.pick(&["U", "D", "L", "R"]), rng.range(1..=20)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
 
-        let mut unique_position = HashSet::new();
-        let mut rope = vec![(1, 1); 10];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2";
+
+    const LARGER_EXAMPLE: &str = "\
+R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20";
+
+    #[test]
+    fn first_part_example() {
+        let input = RopeBridge::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RopeBridge::first_part(&input, &Default::default()),
+            Some(13)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = RopeBridge::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RopeBridge::second_part(&input, &Default::default()),
+            Some(1)
+        );
+    }
 
-        unique_position.insert(rope[LAST]);
-        for command in input {
-            for _ in 0..command.1 {
-                rope[FIRST].0 += command.0 .0;
-                rope[FIRST].1 += command.0 .1;
+    #[test]
+    fn second_part_short_rope() {
+        let input = RopeBridge::parse_str(EXAMPLE).unwrap();
+        let params = RopeParams { knots: 2 };
+        assert_eq!(RopeBridge::second_part(&input, &params), Some(13));
+    }
 
-                for i in 1..10 {
-                    let (x, y) = RopeBridge::get_distance(rope[i], rope[i - 1]);
+    #[test]
+    fn second_part_larger_example() {
+        let input = RopeBridge::parse_str(LARGER_EXAMPLE).unwrap();
+        assert_eq!(
+            RopeBridge::second_part(&input, &Default::default()),
+            Some(36)
+        );
+    }
 
-                    if x.abs() == 2 || y.abs() == 2 {
-                        rope[i].0 += x.normalize();
-                        rope[i].1 += y.normalize();
+    fn reference(input: &str) -> (usize, Option<usize>) {
+        let mut rope = [(0i64, 0i64); 10];
+        let mut second = HashSet::from([rope[1]]);
+        let mut tail = HashSet::from([rope[9]]);
 
-                        if i == LAST {
-                            unique_position.insert(rope[i]);
-                        }
+        for line in input.lines() {
+            let (direction, steps) = line.split_once(' ').unwrap();
+            let (dx, dy) = match direction {
+                "U" => (0, -1),
+                "D" => (0, 1),
+                "L" => (-1, 0),
+                _ => (1, 0),
+            };
+            for _ in 0..steps.parse().unwrap() {
+                rope[0] = (rope[0].0 + dx, rope[0].1 + dy);
+                for i in 1..rope.len() {
+                    let (x, y) = (rope[i - 1].0 - rope[i].0, rope[i - 1].1 - rope[i].1);
+                    if x.abs() > 1 || y.abs() > 1 {
+                        rope[i] = (rope[i].0 + x.clamp(-1, 1), rope[i].1 + y.clamp(-1, 1));
                     }
                 }
+                second.insert(rope[1]);
+                tail.insert(rope[9]);
             }
         }
 
-        Some(unique_position.len())
+        (second.len(), Some(tail.len()))
     }
-}
 
-trait Normalizer {
-    fn normalize(self) -> Self;
-}
-
-impl Normalizer for i64 {
-    fn normalize(self) -> Self {
-        if self != 0 {
-            self / self.abs()
-        } else {
-            0
-        }
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<RopeBridge>(200, reference);
     }
 }
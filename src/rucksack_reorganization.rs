@@ -1,10 +1,74 @@
-use crate::Problem;
+use crate::generate::{Generate, Rng};
+use crate::parse::{self, Line, ParseError};
+use crate::problem::Problem;
+use crate::registry::Part;
+use crate::single_pass::SinglePass;
 use std::collections::HashSet;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
 pub struct RucksackReorganization;
 
+impl RucksackReorganization {
+    /// Items of a rucksack and the priority of the item found in both its compartments.
+    fn parse_rucksack<'a>(line: &Line<'a>) -> Result<(&'a str, u64), ParseError> {
+        if let Some((i, c)) = line
+            .text
+            .char_indices()
+            .find(|(_, c)| !c.is_ascii_alphabetic())
+        {
+            return Err(line.error_at(&line.text[i..], format!("unexpected item '{}'", c)));
+        }
+
+        let (l, r) = line.text.split_at(line.text.len() / 2);
+        let shared = item_mask(l) & item_mask(r);
+        if shared == 0 {
+            return Err(line.error("no item in both compartments"));
+        }
+
+        Ok((line.text, shared.trailing_zeros() as u64))
+    }
+}
+
+/// Items common to the rucksacks of the current group of three elves.
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    size: usize,
+    common: u64,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            common: u64::MAX,
+        }
+    }
+}
+
+impl Group {
+    /// Adds the rucksack of `line`, returning the priority of the badge once the group is
+    /// complete.
+    fn add(&mut self, line: &Line, items: &str) -> Result<Option<u64>, ParseError> {
+        self.size += 1;
+        self.common &= item_mask(items);
+        if self.size < 3 {
+            return Ok(None);
+        }
+
+        let badge = self.common;
+        *self = Self::default();
+        match badge {
+            0 => Err(line.error("no badge common to the group")),
+            badge => Ok(Some(badge.trailing_zeros() as u64)),
+        }
+    }
+
+    /// Whether every rucksack added so far belongs to a complete group.
+    fn is_complete(self) -> bool {
+        self.size == 0
+    }
+}
+
 fn score(c: char) -> u64 {
     let uppercase_bonus = if c.is_uppercase() { 26 } else { 0 };
 
@@ -16,39 +80,62 @@ impl Problem for RucksackReorganization {
     type InputData = Vec<String>;
     type OutputDataFirstPart = u64;
     type OutputDataSecondPart = u64;
+    type Params = ();
+
+    /// Rejects rucksacks without an item in both compartments and groups without a badge,
+    /// which neither part has an answer for. A last group of fewer than three elves is
+    /// accepted, only the second part has no answer then.
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let mut rucksacks = vec![];
+        let mut group = Group::default();
+        parse::for_each_line(reader, |line| {
+            let (items, _) = RucksackReorganization::parse_rucksack(&line)?;
+            group.add(&line, items)?;
+            rucksacks.push(items.to_string());
+            Ok(())
+        })?;
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        reader.lines().map(Result::unwrap).collect()
+        Ok(rucksacks)
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        input
-            .into_iter()
-            .map(|items| {
-                let half_len = items.len() / 2;
-                let l = items.chars().take(half_len).collect::<HashSet<char>>();
-                let r = items
-                    .chars()
-                    .skip(half_len)
-                    .take(half_len + 1)
-                    .collect::<HashSet<char>>();
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        Some(
+            input
+                .iter()
+                .map(|items| {
+                    let half_len = items.len() / 2;
+                    let l = items.chars().take(half_len).collect::<HashSet<char>>();
+                    let r = items
+                        .chars()
+                        .skip(half_len)
+                        .take(half_len + 1)
+                        .collect::<HashSet<char>>();
 
-                (l, r)
-            })
-            .fold(0, |acc, (l, r)| {
-                let intersection = l.intersection(&r).collect::<Vec<&char>>();
-                let score = score(*intersection[0]);
+                    (l, r)
+                })
+                .fold(0, |acc, (l, r)| {
+                    let intersection = l.intersection(&r).collect::<Vec<&char>>();
+                    let score = score(*intersection[0]);
 
-                acc + score
-            })
+                    acc + score
+                }),
+        )
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        if !input.len().is_multiple_of(3) {
+            return None;
+        }
+
         Some(
             input
-                .into_iter()
+                .iter()
                 .map(|item| item.chars().collect::<HashSet<char>>())
                 .collect::<Vec<_>>()
                 .chunks(3)
@@ -64,3 +151,180 @@ impl Problem for RucksackReorganization {
         )
     }
 }
+
+/// Set of items as a bitmask, bit `n` standing for the item of priority `n`.
+fn item_mask(items: &str) -> u64 {
+    items.chars().fold(0, |mask, c| mask | 1 << score(c))
+}
+
+impl SinglePass for RucksackReorganization {
+    /// Keeps only the bitmask of common items of the current group of three elves.
+    ///
+    /// Compartments and groups are checked whichever part is asked for, as [`Problem::parse`]
+    /// does.
+    fn solve_single_pass(
+        reader: impl BufRead,
+        _params: &Self::Params,
+        part: Option<Part>,
+    ) -> Result<(Option<u64>, Option<u64>), ParseError> {
+        let (mut misplaced, mut badges) = (0, 0);
+        let mut group = Group::default();
+        parse::for_each_line(reader, |line| {
+            let (items, shared) = RucksackReorganization::parse_rucksack(&line)?;
+            misplaced += shared;
+            badges += group.add(&line, items)?.unwrap_or_default();
+            Ok(())
+        })?;
+
+        Ok((
+            Part::First.is_selected(part).then_some(misplaced),
+            (Part::Second.is_selected(part) && group.is_complete()).then_some(badges),
+        ))
+    }
+}
+
+impl Generate for RucksackReorganization {
+    /// `size` rucksacks, rounded up to whole groups of three.
+    ///
+    /// Every group draws from disjoint pools of items so that each rucksack has exactly one
+    /// item in both compartments and each group exactly one common badge.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut items = ('a'..='z').chain('A'..='Z').collect::<Vec<_>>();
+        let mut rucksacks = vec![];
+
+        for _ in 0..size.div_ceil(3).max(1) {
+            rng.shuffle(&mut items);
+            let (badge, rest) = items.split_first().unwrap();
+
+            for pool in rest.chunks(rest.len() / 3) {
+                let (shared, pool) = pool.split_first().unwrap();
+                let (left_pool, right_pool) = pool.split_at(pool.len() / 2);
+                let extra = rng.below(12);
+
+                let mut left = vec![*shared, *badge];
+                left.extend((0..extra).map(|_| *rng.pick(left_pool)));
+                let mut right = vec![*shared];
+                right.extend((0..=extra).map(|_| *rng.pick(right_pool)));
+                rng.shuffle(&mut left);
+                rng.shuffle(&mut right);
+
+                rucksacks.push(left.into_iter().chain(right).collect::<String>());
+            }
+        }
+
+        rucksacks.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    #[test]
+    fn first_part_example() {
+        let input = RucksackReorganization::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RucksackReorganization::first_part(&input, &Default::default()),
+            Some(157)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = RucksackReorganization::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            RucksackReorganization::second_part(&input, &Default::default()),
+            Some(70)
+        );
+    }
+
+    #[test]
+    fn reject_rucksacks_without_answer() {
+        let err = RucksackReorganization::parse_str("abcd\nabab\nbb").unwrap_err();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (1, "no item in both compartments")
+        );
+        let err = RucksackReorganization::parse_str("abab\ncdcd\nefef").unwrap_err();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (3, "no badge common to the group")
+        );
+    }
+
+    #[test]
+    fn incomplete_group() {
+        let input = RucksackReorganization::parse_str("abac\ncdce").unwrap();
+        assert_eq!(RucksackReorganization::first_part(&input, &()), Some(4));
+        assert_eq!(RucksackReorganization::second_part(&input, &()), None);
+
+        assert_eq!(
+            RucksackReorganization::solve_single_pass_str("abac\ncdce", &(), None),
+            Ok((Some(4), None))
+        );
+        assert_eq!(
+            RucksackReorganization::solve_single_pass_str("abac\ncdce", &(), Some(Part::First)),
+            Ok((Some(4), None))
+        );
+    }
+
+    #[test]
+    fn single_pass_example() {
+        assert_eq!(
+            RucksackReorganization::solve_single_pass_str(EXAMPLE, &(), None),
+            Ok((Some(157), Some(70)))
+        );
+
+        let err =
+            RucksackReorganization::solve_single_pass_str("abcd\nabab\nbb", &(), None).unwrap_err();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (1, "no item in both compartments")
+        );
+    }
+
+    #[test]
+    fn single_pass_matches_parts() {
+        crate::single_pass::assert_matches_parts::<RucksackReorganization>(60);
+    }
+
+    fn reference(input: &str) -> (u64, Option<u64>) {
+        let priority = |item: char| match item {
+            'a'..='z' => item as u64 - 'a' as u64 + 1,
+            _ => item as u64 - 'A' as u64 + 27,
+        };
+        let rucksacks = input.lines().collect::<Vec<_>>();
+
+        let first = rucksacks
+            .iter()
+            .map(|rucksack| {
+                let (left, right) = rucksack.split_at(rucksack.len() / 2);
+                priority(left.chars().find(|&item| right.contains(item)).unwrap())
+            })
+            .sum();
+        let second = rucksacks
+            .chunks(3)
+            .map(|group| {
+                let badge = group[0]
+                    .chars()
+                    .find(|&item| group[1].contains(item) && group[2].contains(item));
+                priority(badge.unwrap())
+            })
+            .sum();
+
+        (first, Some(second))
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<RucksackReorganization>(60, reference);
+    }
+}
@@ -0,0 +1,512 @@
+use std::any::Any;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use advent_of_code_2022::dyn_problem::{Answer, DynProblem};
+use advent_of_code_2022::inputs::{self, Kind};
+use advent_of_code_2022::params::Overrides;
+use advent_of_code_2022::parse::ParseError;
+use advent_of_code_2022::registry::{Answers, Day, Part};
+use advent_of_code_2022::Token;
+
+use crate::memory::{self, Usage};
+
+/// Parsing or one of the parts, the steps every day goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Parse,
+    First,
+    Second,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Parse, Stage::First, Stage::Second];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::First => "part1",
+            Stage::Second => "part2",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Stage> {
+        Stage::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Stage::Parse => "parsing",
+            Stage::First => "part 1",
+            Stage::Second => "part 2",
+        }
+    }
+}
+
+/// Time limit of every stage unless the command line gives another one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    pub parse: Duration,
+    pub first: Duration,
+    pub second: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.first + self.second
+    }
+}
+
+/// Allocations of each stage, see [`memory::measure`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Memory {
+    pub parse: Usage,
+    pub first: Usage,
+    pub second: Usage,
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub answers: Answers,
+    pub timings: Timings,
+    /// `None` unless the counting allocator is installed.
+    pub memory: Option<Memory>,
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Solved(Profile),
+    Failed(String),
+    /// A stage ran past the time limit; the reason names it.
+    TimedOut(String),
+}
+
+/// Solves a day like [`Day::solve`], timing parsing and each part separately and measuring their
+/// allocations. `on_stage` is called as every stage starts.
+///
+/// Only the `part` asked for runs, both when `None`; the other one has no answer, time or
+/// allocations.
+pub fn profile(
+    problem: &dyn DynProblem,
+    filename: &Path,
+    overrides: &Overrides,
+    part: Option<Part>,
+    on_stage: &mut dyn FnMut(Stage),
+) -> Result<Profile, ParseError> {
+    let params = problem.params(overrides).map_err(ParseError::input)?;
+
+    on_stage(Stage::Parse);
+    let start = Instant::now();
+    let (data, parse_memory) = memory::measure(|| problem.read_file(filename));
+    let parse = start.elapsed();
+    let data = data?;
+
+    let mut run = |stage, solve: &dyn Fn() -> Option<Answer>| {
+        on_stage(stage);
+        let start = Instant::now();
+        let (answer, usage) = memory::measure(solve);
+        (answer, start.elapsed(), usage)
+    };
+    let skipped = (None, Duration::ZERO, Usage::default());
+
+    let (first, first_time, first_memory) = if Part::First.is_selected(part) {
+        run(Stage::First, &|| problem.first_part(&*data, &*params))
+    } else {
+        skipped.clone()
+    };
+    let (second, second_time, second_memory) = if Part::Second.is_selected(part) {
+        run(Stage::Second, &|| problem.second_part(&*data, &*params))
+    } else {
+        skipped
+    };
+
+    Ok(Profile {
+        answers: Answers {
+            first: first.map(|answer| answer.to_string()),
+            second: second.map(|answer| answer.to_string()),
+        },
+        timings: Timings {
+            parse,
+            first: first_time,
+            second: second_time,
+        },
+        memory: memory::is_counting().then_some(Memory {
+            parse: parse_memory,
+            first: first_memory,
+            second: second_memory,
+        }),
+    })
+}
+
+/// Profiles a day, or only its `part` when given, reporting a parse error or panic as a
+/// failure.
+///
+/// With a `timeout` the day runs on a worker thread and every stage gets that long; the
+/// worker of a stage running late is cancelled and left behind, see [`Token`].
+pub fn run_day(
+    day: &Day,
+    input: &Path,
+    overrides: &Overrides,
+    part: Option<Part>,
+    timeout: Option<Duration>,
+) -> Outcome {
+    let Some(timeout) = timeout else {
+        return match isolate(|| profile(day.problem, input, overrides, part, &mut |_| {})) {
+            Ok(profile) => Outcome::Solved(profile),
+            Err(reason) => Outcome::Failed(reason),
+        };
+    };
+
+    enum Event {
+        Started(Stage),
+        Finished(Result<Profile, String>),
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let token = Token::default();
+    let worker = {
+        let (problem, input, overrides) = (day.problem, input.to_path_buf(), overrides.clone());
+        let token = token.clone();
+        move || {
+            token.install();
+            let started = sender.clone();
+            let result = isolate(|| {
+                profile(problem, &input, &overrides, part, &mut |stage| {
+                    let _ = started.send(Event::Started(stage));
+                })
+            });
+            let _ = sender.send(Event::Finished(result));
+        }
+    };
+    if let Err(err) = thread::Builder::new()
+        .name(day.name.to_string())
+        .spawn(worker)
+    {
+        return Outcome::Failed(format!("cannot start a worker thread: {}", err));
+    }
+
+    let mut stage = Stage::Parse;
+    loop {
+        match receiver.recv_timeout(timeout) {
+            Ok(Event::Started(next)) => stage = next,
+            Ok(Event::Finished(Ok(profile))) => return Outcome::Solved(profile),
+            Ok(Event::Finished(Err(reason))) => return Outcome::Failed(reason),
+            Err(RecvTimeoutError::Timeout) => {
+                token.cancel();
+                return Outcome::TimedOut(format!(
+                    "{} took longer than {}",
+                    stage.describe(),
+                    format_duration(timeout)
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Outcome::Failed("worker thread stopped without an answer".to_string())
+            }
+        }
+    }
+}
+
+/// Profiles the given days, reading inputs from `dir`, with every stage limited to `timeout`.
+///
+/// A day that fails to parse, panics or runs out of time is reported as such and does not
+/// stop the others.
+pub fn run_days(
+    days: impl IntoIterator<Item = &'static Day>,
+    dir: &Path,
+    timeout: Option<Duration>,
+) -> Vec<(&'static Day, Outcome)> {
+    with_silent_panics(|| {
+        days.into_iter()
+            .map(|day| {
+                let outcome = match inputs::resolve(day, dir, Kind::Puzzle) {
+                    Ok(input) => run_day(day, &input, &Overrides::default(), None, timeout),
+                    Err(err) => Outcome::Failed(err.to_string()),
+                };
+                (day, outcome)
+            })
+            .collect()
+    })
+}
+
+/// Runs `f` with the default panic message printing disabled.
+pub fn with_silent_panics<R>(f: impl FnOnce() -> R) -> R {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = f();
+    panic::set_hook(hook);
+
+    result
+}
+
+/// Runs `f`, turning a parse error or a panic into a failure reason.
+pub fn isolate<R>(f: impl FnOnce() -> Result<R, ParseError>) -> Result<R, String> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(err)) => Err(err.to_string()),
+        Err(payload) => Err(format!("panicked: {}", panic_message(&payload))),
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+pub fn format_report(results: &[(&'static Day, Outcome)]) -> String {
+    let mut report = String::new();
+    let mut total = Timings::default();
+    let mut failures = vec![];
+
+    writeln!(
+        report,
+        "{:>3}  {:<24} {:>12} {:>12} {:>12} {:>12}  {:<6}  Answers",
+        "Day", "Name", "Parse", "Part 1", "Part 2", "Total", "Status"
+    )
+    .unwrap();
+    for (day, outcome) in results {
+        match outcome {
+            Outcome::Solved(Profile {
+                answers, timings, ..
+            }) => {
+                writeln!(
+                    report,
+                    "{:>3}  {:<24} {:>12} {:>12} {:>12} {:>12}  {:<6}  {} / {}",
+                    day.number,
+                    day.name,
+                    format_duration(timings.parse),
+                    format_duration(timings.first),
+                    format_duration(timings.second),
+                    format_duration(timings.total()),
+                    "ok",
+                    format_answer(answers.first.as_ref()),
+                    format_answer(answers.second.as_ref()),
+                )
+                .unwrap();
+                total.parse += timings.parse;
+                total.first += timings.first;
+                total.second += timings.second;
+            }
+            Outcome::Failed(reason) => {
+                writeln!(
+                    report,
+                    "{:>3}  {:<24} {:>12} {:>12} {:>12} {:>12}  FAILED",
+                    day.number, day.name, "-", "-", "-", "-"
+                )
+                .unwrap();
+                failures.push((day, "failed", reason));
+            }
+            Outcome::TimedOut(reason) => {
+                writeln!(
+                    report,
+                    "{:>3}  {:<24} {:>12} {:>12} {:>12} {:>12}  TIMEOUT",
+                    day.number, day.name, "-", "-", "-", "-"
+                )
+                .unwrap();
+                failures.push((day, "timed out", reason));
+            }
+        }
+    }
+    writeln!(
+        report,
+        "{:>3}  {:<24} {:>12} {:>12} {:>12} {:>12}",
+        "",
+        "Total",
+        format_duration(total.parse),
+        format_duration(total.first),
+        format_duration(total.second),
+        format_duration(total.total()),
+    )
+    .unwrap();
+
+    let memory = results
+        .iter()
+        .filter_map(|(day, outcome)| match outcome {
+            Outcome::Solved(Profile {
+                memory: Some(memory),
+                ..
+            }) => Some((day, memory)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !memory.is_empty() {
+        writeln!(
+            report,
+            "\n{:>3}  {:<24} {:>21} {:>21} {:>21}",
+            "Day", "Memory (peak / total)", "Parse", "Part 1", "Part 2"
+        )
+        .unwrap();
+        for (day, memory) in memory {
+            writeln!(
+                report,
+                "{:>3}  {:<24} {:>21} {:>21} {:>21}",
+                day.number,
+                day.name,
+                format_usage(memory.parse),
+                format_usage(memory.first),
+                format_usage(memory.second),
+            )
+            .unwrap();
+        }
+    }
+
+    for (day, status, reason) in failures {
+        write!(
+            report,
+            "\nDay {} ({}) {}: {}",
+            day.number, day.name, status, reason
+        )
+        .unwrap();
+    }
+
+    report
+}
+
+/// Multi-line answers (e.g. the CRT drawing) do not fit into a table row.
+fn format_answer(answer: Option<&String>) -> String {
+    match answer {
+        Some(answer) if answer.contains('\n') => format!("<{} lines>", answer.lines().count()),
+        Some(answer) => answer.clone(),
+        None => "-".to_string(),
+    }
+}
+
+fn format_usage(usage: Usage) -> String {
+    format!(
+        "{} / {}",
+        memory::format_bytes(usage.peak),
+        memory::format_bytes(usage.total)
+    )
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    format!("{:.2?}", duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2022::checkpoint;
+    use advent_of_code_2022::problem::Problem;
+    use advent_of_code_2022::registry::DAYS;
+    use std::io::BufRead;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static STOPPED: AtomicBool = AtomicBool::new(false);
+
+    /// Never finishes the first part unless cancelled; it has no generator.
+    struct Spin;
+
+    impl Problem for Spin {
+        type InputData = ();
+        type OutputDataFirstPart = usize;
+        type OutputDataSecondPart = usize;
+        type Params = ();
+
+        fn parse(_: impl BufRead) -> Result<(), ParseError> {
+            Ok(())
+        }
+
+        fn first_part(_: &(), _: &()) -> Option<usize> {
+            struct Stopped;
+            impl Drop for Stopped {
+                fn drop(&mut self) {
+                    STOPPED.store(true, Ordering::Relaxed);
+                }
+            }
+
+            let _stopped = Stopped;
+            loop {
+                checkpoint();
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        fn second_part(_: &(), _: &()) -> Option<usize> {
+            None
+        }
+    }
+
+    #[test]
+    fn report_timeout_and_cancel_worker() {
+        let day = Day {
+            problem: &Spin,
+            ..DAYS[0]
+        };
+        let input = std::env::temp_dir().join(format!("aoc-2022-spin-{}.txt", std::process::id()));
+        std::fs::write(&input, "").unwrap();
+
+        let outcome = run_day(
+            &day,
+            &input,
+            &Overrides::default(),
+            None,
+            Some(Duration::from_millis(50)),
+        );
+        std::fs::remove_file(input).unwrap();
+        let Outcome::TimedOut(reason) = outcome else {
+            panic!("expected a timeout, got {:?}", outcome);
+        };
+        assert_eq!(reason, "part 1 took longer than 50.00ms");
+
+        let start = Instant::now();
+        while !STOPPED.load(Ordering::Relaxed) {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "worker kept running"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn failures_on_worker_thread() {
+        let day = &DAYS[0];
+        let timeout = Some(DEFAULT_TIMEOUT);
+
+        let outcome = run_day(
+            day,
+            Path::new("missing.txt"),
+            &Overrides::default(),
+            None,
+            timeout,
+        );
+        assert!(matches!(outcome, Outcome::Failed(reason) if reason.contains("missing.txt")));
+    }
+
+    #[test]
+    fn skip_the_other_part() {
+        let day = Day {
+            problem: &Spin,
+            ..DAYS[0]
+        };
+        let input = std::env::temp_dir().join(format!("aoc-2022-skip-{}.txt", std::process::id()));
+        std::fs::write(&input, "").unwrap();
+
+        // The first part would never finish
+        let mut stages = vec![];
+        let profile = profile(
+            day.problem,
+            &input,
+            &Overrides::default(),
+            Some(Part::Second),
+            &mut |stage| stages.push(stage),
+        )
+        .unwrap();
+        std::fs::remove_file(input).unwrap();
+
+        assert_eq!(stages, [Stage::Parse, Stage::Second]);
+        assert_eq!(profile.answers.first, None);
+        assert_eq!(profile.timings.first, Duration::ZERO);
+    }
+}
@@ -0,0 +1,103 @@
+use std::any::Any;
+use std::io::BufRead;
+
+use crate::dyn_problem::{self, Answer};
+use crate::parse::ParseError;
+use crate::problem::{Problem, Solution};
+use crate::registry::Part;
+
+/// Days that can solve both parts while reading their input, without ever holding all of it.
+///
+/// The answers are the same as [`Problem::first_part`] and [`Problem::second_part`] on the
+/// parsed input. Only the `part` asked for is solved, both when `None`; the other one is
+/// `None`.
+pub trait SinglePass: Problem {
+    fn solve_single_pass(
+        reader: impl BufRead,
+        params: &Self::Params,
+        part: Option<Part>,
+    ) -> Result<Solution<Self>, ParseError>;
+
+    fn solve_single_pass_str(
+        input: &str,
+        params: &Self::Params,
+        part: Option<Part>,
+    ) -> Result<Solution<Self>, ParseError> {
+        Self::solve_single_pass(input.as_bytes(), params, part)
+    }
+}
+
+/// Object-safe view of a [`SinglePass`] day, as kept in the registry next to its
+/// [`DynProblem`](crate::dyn_problem::DynProblem); `params` must come from the same day.
+pub trait DynSinglePass: Sync {
+    fn solve(
+        &self,
+        reader: &mut dyn BufRead,
+        params: &dyn Any,
+        part: Option<Part>,
+    ) -> Result<(Option<Answer>, Option<Answer>), ParseError>;
+}
+
+impl<T> DynSinglePass for T
+where
+    T: SinglePass + Sync,
+    T::Params: 'static,
+    T::OutputDataFirstPart: Into<Answer>,
+    T::OutputDataSecondPart: Into<Answer>,
+{
+    fn solve(
+        &self,
+        reader: &mut dyn BufRead,
+        params: &dyn Any,
+        part: Option<Part>,
+    ) -> Result<(Option<Answer>, Option<Answer>), ParseError> {
+        let (first, second) =
+            T::solve_single_pass(reader, dyn_problem::downcast::<T::Params>(params), part)?;
+
+        Ok((first.map(Into::into), second.map(Into::into)))
+    }
+}
+
+/// Checks the single-pass solver against the regular parts on inputs generated from several
+/// seeds.
+#[cfg(test)]
+pub(crate) fn assert_matches_parts<T: SinglePass + crate::generate::Generate>(size: usize)
+where
+    T::OutputDataFirstPart: PartialEq + std::fmt::Debug,
+    T::OutputDataSecondPart: PartialEq + std::fmt::Debug,
+{
+    for seed in 0..16 {
+        let input = T::generate(&mut crate::generate::Rng::new(seed), size);
+        let data = T::parse_str(&input).unwrap();
+        let (first, second) = (
+            T::first_part(&data, &Default::default()),
+            T::second_part(&data, &Default::default()),
+        );
+        let solve = |part| T::solve_single_pass_str(&input, &Default::default(), part).unwrap();
+
+        assert_eq!(solve(None), (first, second), "seed {}, size {}", seed, size);
+        assert_eq!(solve(Some(Part::First)).1, None);
+        assert_eq!(solve(Some(Part::Second)), (None, solve(None).1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{self, DAYS};
+
+    #[test]
+    fn registered_days() {
+        let days = DAYS
+            .iter()
+            .filter(|day| day.single_pass.is_some())
+            .map(|day| day.number)
+            .collect::<Vec<_>>();
+        assert_eq!(days, [1, 3, 4]);
+
+        let err = registry::find("9")
+            .unwrap()
+            .solve_single_pass("day09.txt".as_ref(), &Default::default(), None)
+            .unwrap_err();
+        assert_eq!(err.message, "Day 9 has no single-pass solver");
+    }
+}
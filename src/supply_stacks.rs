@@ -1,7 +1,8 @@
-use crate::Problem;
+use crate::generate::{Generate, Rng};
+use crate::parse::{self, ParseError};
+use crate::problem::Problem;
 use std::collections::VecDeque;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 
 pub struct SupplyStacks;
 
@@ -10,54 +11,118 @@ pub struct SupplyData(Vec<Vec<char>>, Vec<SupplyCommand>);
 #[derive(Debug, Clone)]
 pub struct SupplyCommand(u64, usize, usize);
 
+impl SupplyStacks {
+    /// Draws the stacks the way the input does, with the stack numbers at the bottom.
+    fn render(crates: &[Vec<char>]) -> String {
+        let height = crates.iter().map(Vec::len).max().unwrap_or_default();
+        let mut lines = (0..height)
+            .rev()
+            .map(|level| {
+                crates
+                    .iter()
+                    .map(|stack| match stack.get(level) {
+                        Some(crt) => format!("[{}]", crt),
+                        None => "   ".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        lines.push(
+            (1..=crates.len())
+                .map(|number| format!(" {} ", number))
+                .collect::<Vec<_>>()
+                .join(" ")
+                .trim_end()
+                .to_string(),
+        );
+
+        lines.join("\n")
+    }
+
+    /// Crates on top of every stack, a space standing for an emptied stack.
+    fn tops(crates: &[Vec<char>]) -> String {
+        crates
+            .iter()
+            .map(|stack| stack.last().copied().unwrap_or(' '))
+            .collect()
+    }
+}
+
 impl Problem for SupplyStacks {
     type InputData = SupplyData;
     type OutputDataFirstPart = String;
     type OutputDataSecondPart = String;
+    type Params = ();
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-
-        let mut lines: Vec<String> = reader.lines().map(Result::unwrap).collect();
-        let mut splitter = lines.split_mut(|s| s.is_empty());
-
-        let stacks = splitter.next().unwrap();
-        stacks.reverse();
-        let text_commands = splitter.next().unwrap();
-
-        let mut crates = vec![vec![]; stacks[0].split(' ').filter(|el| !el.is_empty()).count()];
-        for stack in stacks.iter().skip(1) {
-            stack
-                .chars()
-                .collect::<Vec<char>>()
-                .chunks(4)
-                .into_iter()
-                .map(|t| t[1])
-                .enumerate()
-                .for_each(|(i, t)| {
-                    if !t.is_whitespace() {
-                        crates[i].push(t);
-                    }
-                });
+    /// Rejects moves taking more crates than their stack holds at that point.
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let lines = parse::read_lines(reader)?;
+        let lines = parse::numbered(&lines).collect::<Vec<_>>();
+        let mut sections = parse::sections(&lines);
+
+        let stacks = sections.next().unwrap_or_default();
+        let text_commands = sections.next().unwrap_or_default();
+        if let Some(extra) = sections.next() {
+            return Err(extra[0].error("unexpected section after the moves"));
         }
+        let Some((numbers, stacks)) = stacks.split_last() else {
+            return Err(ParseError::input("missing stack drawing"));
+        };
 
+        let mut crates = vec![vec![]; numbers.text.split(' ').filter(|el| !el.is_empty()).count()];
+        for stack in stacks.iter().rev() {
+            for (i, t) in stack.text.chars().skip(1).step_by(4).enumerate() {
+                if t.is_whitespace() {
+                    continue;
+                }
+                let Some(crt) = crates.get_mut(i) else {
+                    return Err(stack.error_at(
+                        &stack.text[(4 * i).min(stack.text.len())..],
+                        "crate outside of numbered stacks",
+                    ));
+                };
+                crt.push(t);
+            }
+        }
+
+        let mut heights = crates.iter().map(Vec::len).collect::<Vec<_>>();
         let mut commands = vec![];
         for command in text_commands {
-            let tokens = command.split(' ').collect::<Vec<_>>();
-            commands.push(SupplyCommand(
-                tokens[1].parse().unwrap(),
-                tokens[3].parse::<usize>().unwrap() - 1,
-                tokens[5].parse::<usize>().unwrap() - 1,
-            ));
+            let [count, from, to] = command.fields(command.text, "move {} from {} to {}")?;
+            let stack_index = |token| -> Result<usize, ParseError> {
+                match command.parse::<usize>(token)? {
+                    index @ 1.. if index <= crates.len() => Ok(index - 1),
+                    _ => Err(command.error_at(token, format!("no stack '{}'", token))),
+                }
+            };
+            let (moved, source, target) = (
+                command.parse::<usize>(count)?,
+                stack_index(from)?,
+                stack_index(to)?,
+            );
+            if moved > heights[source] {
+                return Err(command.error_at(
+                    count,
+                    format!("only {} crates on stack {}", heights[source], from),
+                ));
+            }
+            heights[source] -= moved;
+            heights[target] += moved;
+            commands.push(SupplyCommand(moved as u64, source, target));
         }
 
-        SupplyData(crates, commands)
+        Ok(SupplyData(crates, commands))
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        let mut crates = input.0;
-        let commands = input.1;
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        let mut crates = input.0.clone();
+        let commands = &input.1;
 
         for command in commands {
             for _ in 0..command.0 {
@@ -66,15 +131,15 @@ impl Problem for SupplyStacks {
             }
         }
 
-        crates.into_iter().fold(String::new(), |mut acc, crt| {
-            acc.push(*crt.last().unwrap());
-            acc
-        })
+        Some(SupplyStacks::tops(&crates))
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        let mut crates = input.0;
-        let commands = input.1;
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        let mut crates = input.0.clone();
+        let commands = &input.1;
 
         for command in commands {
             let mut queue = VecDeque::new();
@@ -86,9 +151,208 @@ impl Problem for SupplyStacks {
             }
         }
 
-        Some(crates.into_iter().fold(String::new(), |mut acc, crt| {
-            acc.push(*crt.last().unwrap());
-            acc
-        }))
+        Some(SupplyStacks::tops(&crates))
+    }
+
+    fn describe(input: &Self::InputData, _params: &Self::Params) -> Option<String> {
+        Some(format!(
+            "{}\n\n{} moves",
+            SupplyStacks::render(&input.0),
+            input.1.len()
+        ))
+    }
+
+    /// The stacks after every move of the first part's crane, which moves one crate at a time.
+    fn visualize(
+        input: &Self::InputData,
+        _params: &Self::Params,
+        frame: &mut dyn FnMut(String),
+    ) -> bool {
+        let mut crates = input.0.clone();
+        frame(SupplyStacks::render(&crates));
+
+        for command in &input.1 {
+            for _ in 0..command.0 {
+                let pop = crates[command.1].pop().unwrap();
+                crates[command.2].push(pop);
+            }
+            frame(format!(
+                "move {} from {} to {}\n\n{}",
+                command.0,
+                command.1 + 1,
+                command.2 + 1,
+                SupplyStacks::render(&crates)
+            ));
+        }
+
+        true
+    }
+}
+
+impl Generate for SupplyStacks {
+    /// Nine stacks and `size` moves; moves never empty a stack, so every stack has a top crate.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        const STACKS: usize = 9;
+
+        let mut stacks = (0..STACKS)
+            .map(|i| {
+                // A stack taller than one crate guarantees a legal move
+                let height = rng.range(if i == 0 { 2 } else { 1 }..=12);
+                (0..height)
+                    .map(|_| (b'A' + rng.below(26) as u8) as char)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut lines = vec![];
+
+        let height = stacks.iter().map(Vec::len).max().unwrap();
+        for level in (0..height).rev() {
+            let row = stacks
+                .iter()
+                .map(|stack| {
+                    stack
+                        .get(level)
+                        .map_or("   ".to_string(), |crt| format!("[{}]", crt))
+                })
+                .collect::<Vec<_>>();
+            lines.push(row.join(" "));
+        }
+        lines.push(
+            (1..=STACKS)
+                .map(|number| format!(" {} ", number))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        lines.push(String::new());
+
+        for _ in 0..size {
+            let sources = (0..STACKS)
+                .filter(|&i| stacks[i].len() > 1)
+                .collect::<Vec<_>>();
+            let from = *rng.pick(&sources);
+            let to = (from + 1 + rng.below(STACKS - 1)) % STACKS;
+            let count = rng.range(1..=stacks[from].len() as i64 - 1) as usize;
+
+            let remaining = stacks[from].len() - count;
+            let moved = stacks[from].split_off(remaining);
+            stacks[to].extend(moved);
+            lines.push(format!("move {} from {} to {}", count, from + 1, to + 1));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "    [D]
+[N] [C]
+[Z] [M] [P]
+ 1   2   3
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    #[test]
+    fn first_part_example() {
+        let input = SupplyStacks::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            SupplyStacks::first_part(&input, &Default::default()).as_deref(),
+            Some("CMZ")
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = SupplyStacks::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            SupplyStacks::second_part(&input, &Default::default()),
+            Some("MCD".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_moving_missing_crates() {
+        let err = SupplyStacks::parse_str("[A]\n 1   2\n\nmove 1 from 1 to 2\nmove 3 from 2 to 1")
+            .err()
+            .unwrap();
+        assert_eq!(
+            (err.line, err.message.as_str()),
+            (5, "only 1 crates on stack 2")
+        );
+    }
+
+    #[test]
+    fn emptied_stack() {
+        let input = SupplyStacks::parse_str("[A]\n 1   2\n\nmove 1 from 1 to 2").unwrap();
+        assert_eq!(
+            SupplyStacks::first_part(&input, &Default::default()).as_deref(),
+            Some(" A")
+        );
+        assert_eq!(
+            SupplyStacks::second_part(&input, &Default::default()).as_deref(),
+            Some(" A")
+        );
+    }
+
+    #[test]
+    fn visualize_example() {
+        let input = SupplyStacks::parse_str(EXAMPLE).unwrap();
+        let mut frames = vec![];
+        assert!(SupplyStacks::visualize(
+            &input,
+            &Default::default(),
+            &mut |frame| frames.push(frame)
+        ));
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0], EXAMPLE.split("\n\n").next().unwrap());
+        assert!(frames[4].starts_with("move 1 from 1 to 2\n"));
+    }
+
+    fn reference(input: &str) -> (String, Option<String>) {
+        let (drawing, moves) = input.split_once("\n\n").unwrap_or((input, ""));
+        let mut rows = drawing.lines().rev();
+        let count = rows.next().unwrap().split_whitespace().count();
+        let mut stacks = vec![vec![]; count];
+        for row in rows {
+            for (i, stack) in stacks.iter_mut().enumerate() {
+                match row.chars().nth(4 * i + 1) {
+                    Some(' ') | None => {}
+                    Some(crt) => stack.push(crt),
+                }
+            }
+        }
+
+        let (mut one_by_one, mut at_once) = (stacks.clone(), stacks);
+        for line in moves.lines() {
+            let numbers = line
+                .split(' ')
+                .filter_map(|token| token.parse::<usize>().ok())
+                .collect::<Vec<_>>();
+            let (count, from, to) = (numbers[0], numbers[1] - 1, numbers[2] - 1);
+
+            for _ in 0..count {
+                let crt = one_by_one[from].pop().unwrap();
+                one_by_one[to].push(crt);
+            }
+            let split = at_once[from].len() - count;
+            let moved = at_once[from].split_off(split);
+            at_once[to].extend(moved);
+        }
+
+        let tops = |stacks: Vec<Vec<char>>| -> String {
+            stacks.iter().map(|stack| *stack.last().unwrap()).collect()
+        };
+        (tops(one_by_one), Some(tops(at_once)))
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<SupplyStacks>(100, reference);
     }
 }
@@ -1,64 +1,45 @@
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 use std::ops::ControlFlow::{Break, Continue};
-use std::path::Path;
 
+use crate::cancel;
+use crate::generate::{Generate, Rng};
+use crate::grid::Grid;
+use crate::parse::ParseError;
 use crate::problem::Problem;
 
 pub struct TreetopTreeHouse;
 
 impl TreetopTreeHouse {
-    fn is_hide_tree((i, j): (usize, usize), field: &[Vec<u8>]) -> bool {
-        let current = field[i][j];
+    fn is_hide_tree((x, y): (usize, usize), field: &Grid<u8>) -> bool {
+        let current = field[(x, y)];
+        let row = field.row(y);
 
         //Left
-        if *field[i][..j].iter().max().unwrap() < current {
+        if *row[..x].iter().max().unwrap() < current {
             return false;
         }
 
         //Right
-        if *field[i][(j + 1)..].iter().max().unwrap() < current {
+        if *row[(x + 1)..].iter().max().unwrap() < current {
             return false;
         }
 
         //Top
-        if field[..i].iter().map(|el| el[j]).max().unwrap() < current {
+        if *field.column(x).take(y).max().unwrap() < current {
             return false;
         }
 
         //Bottom
-        if field[(i + 1)..].iter().map(|el| el[j]).max().unwrap() < current {
+        if *field.column(x).skip(y + 1).max().unwrap() < current {
             return false;
         }
 
         true
     }
 
-    //TODO: Helper function to reduce duplicate
-    fn count_visible_tree_in_row(
-        current: u8,
-        index: usize,
-        mut range: impl Iterator<Item = usize>,
-        field: &[Vec<u8>],
-    ) -> usize {
-        let (Continue(count) | Break(count)) = range.try_fold(0, |acc, k| {
-            if field[index][k] < current {
-                Continue(acc + 1)
-            } else {
-                Break(acc + 1)
-            }
-        });
-
-        count
-    }
-
-    fn count_visible_tree_in_col(
-        current: u8,
-        index: usize,
-        mut range: impl Iterator<Item = usize>,
-        field: &[Vec<u8>],
-    ) -> usize {
-        let (Continue(count) | Break(count)) = range.try_fold(0, |acc, k| {
-            if field[k][index] < current {
+    fn count_visible_trees<'a>(current: u8, mut trees: impl Iterator<Item = &'a u8>) -> usize {
+        let (Continue(count) | Break(count)) = trees.try_fold(0, |acc, &tree| {
+            if tree < current {
                 Continue(acc + 1)
             } else {
                 Break(acc + 1)
@@ -70,59 +51,55 @@ impl TreetopTreeHouse {
 }
 
 impl Problem for TreetopTreeHouse {
-    type InputData = Vec<Vec<u8>>;
+    type InputData = Grid<u8>;
     type OutputDataFirstPart = usize;
     type OutputDataSecondPart = usize;
-
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-
-        reader
-            .lines()
-            .map(Result::unwrap)
-            .fold(vec![], |mut acc, el| {
-                acc.push(
-                    el.chars()
-                        .map(|el| el.to_digit(10).unwrap())
-                        .map(|el| el as u8)
-                        .collect::<Vec<_>>(),
-                );
-
-                acc
-            })
+    type Params = ();
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        Grid::parse(reader, |_, el| {
+            el.to_digit(10)
+                .map(|el| el as u8)
+                .ok_or_else(|| format!("unexpected tree '{}'", el))
+        })
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        let mut count = 0;
-        //Borders
-        count += input.len() * 2;
-        count += (input[0].len() - 2) * 2;
-
-        for i in 1..(input.len() - 1) {
-            for j in 1..(input[i].len() - 1) {
-                if !Self::is_hide_tree((i, j), &input) {
+    fn first_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        let (width, height) = (input.width(), input.height());
+        //Borders, which are all of a forest less than 3 trees wide or high
+        let mut count = width * height - width.saturating_sub(2) * height.saturating_sub(2);
+
+        for y in 1..height.saturating_sub(1) {
+            cancel::checkpoint();
+            for x in 1..width.saturating_sub(1) {
+                if !Self::is_hide_tree((x, y), input) {
                     count += 1;
                 }
             }
         }
 
-        count
+        Some(count)
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
+    fn second_part(
+        input: &Self::InputData,
+        _params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
         let mut max = 0;
 
-        for i in 0..input.len() {
-            for j in 0..input[i].len() {
-                let current = input[i][j];
+        for y in 0..input.height() {
+            cancel::checkpoint();
+            let row = input.row(y);
+            for x in 0..input.width() {
+                let current = row[x];
 
-                let left_count = Self::count_visible_tree_in_row(current, i, (0..j).rev(), &input);
-                let right_count =
-                    Self::count_visible_tree_in_row(current, i, (j + 1)..input[i].len(), &input);
-                let top_count = Self::count_visible_tree_in_col(current, j, (0..i).rev(), &input);
-                let bottom_count =
-                    Self::count_visible_tree_in_col(current, j, (i + 1)..input.len(), &input);
+                let left_count = Self::count_visible_trees(current, row[..x].iter().rev());
+                let right_count = Self::count_visible_trees(current, row[(x + 1)..].iter());
+                let top_count = Self::count_visible_trees(current, input.column(x).take(y).rev());
+                let bottom_count = Self::count_visible_trees(current, input.column(x).skip(y + 1));
 
                 max = max.max(right_count * left_count * top_count * bottom_count);
             }
@@ -131,3 +108,98 @@ impl Problem for TreetopTreeHouse {
         Some(max)
     }
 }
+
+impl Generate for TreetopTreeHouse {
+    /// A `size` by `size` forest (at least 3 by 3).
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let size = size.max(3);
+        let mut forest = Grid::new(size, size, 0);
+        for position in forest.positions() {
+            forest[position] = rng.below(10) as u32;
+        }
+
+        forest.render(|&tree| char::from_digit(tree, 10).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+30373
+25512
+65332
+33549
+35390";
+
+    #[test]
+    fn first_part_example() {
+        let input = TreetopTreeHouse::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            TreetopTreeHouse::first_part(&input, &Default::default()),
+            Some(21)
+        );
+    }
+
+    #[test]
+    fn narrow_forests() {
+        let row = TreetopTreeHouse::parse_str("12345").unwrap();
+        assert_eq!(TreetopTreeHouse::first_part(&row, &()), Some(5));
+        let column = TreetopTreeHouse::parse_str("1\n2\n3").unwrap();
+        assert_eq!(TreetopTreeHouse::first_part(&column, &()), Some(3));
+        let two_rows = TreetopTreeHouse::parse_str("999\n111").unwrap();
+        assert_eq!(TreetopTreeHouse::first_part(&two_rows, &()), Some(6));
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = TreetopTreeHouse::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            TreetopTreeHouse::second_part(&input, &Default::default()),
+            Some(8)
+        );
+    }
+
+    fn reference(input: &str) -> (usize, Option<usize>) {
+        let forest = input
+            .lines()
+            .map(|line| line.bytes().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let (height, width) = (forest.len() as i64, forest[0].len() as i64);
+        let (mut visible, mut best) = (0, 0);
+
+        for y in 0..height {
+            for x in 0..width {
+                let tree = forest[y as usize][x as usize];
+                let mut seen = false;
+                let mut score = 1;
+                for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                    let (mut cx, mut cy) = (x + dx, y + dy);
+                    let mut distance = 0;
+                    let mut blocked = false;
+                    while (0..width).contains(&cx) && (0..height).contains(&cy) {
+                        distance += 1;
+                        if forest[cy as usize][cx as usize] >= tree {
+                            blocked = true;
+                            break;
+                        }
+                        cx += dx;
+                        cy += dy;
+                    }
+                    seen |= !blocked;
+                    score *= distance;
+                }
+                visible += seen as usize;
+                best = best.max(score);
+            }
+        }
+
+        (visible, Some(best))
+    }
+
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<TreetopTreeHouse>(30, reference);
+    }
+}
@@ -1,25 +1,69 @@
+use crate::cancel;
+use crate::generate::{Generate, Rng};
+use crate::params::{self, Params};
+use crate::parse::{self, ParseError};
 use crate::problem::Problem;
 use std::collections::{HashSet, VecDeque};
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
+
+#[derive(Debug, Clone)]
+pub struct TuningParams {
+    /// Distinct characters of a start-of-packet marker.
+    pub packet_marker: usize,
+    /// Distinct characters of a start-of-message marker.
+    pub message_marker: usize,
+}
+
+impl Default for TuningParams {
+    fn default() -> Self {
+        Self {
+            packet_marker: 4,
+            message_marker: 14,
+        }
+    }
+}
+
+impl Params for TuningParams {
+    const NAMES: &'static [&'static str] = &["packet_marker", "message_marker"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "packet_marker" => self.packet_marker = marker_size(name, value)?,
+            "message_marker" => self.message_marker = marker_size(name, value)?,
+            _ => return Err(Self::unknown(name)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Marker sizes are limited to the 26 lowercase letters a signal is made of.
+fn marker_size(name: &str, value: &str) -> Result<usize, String> {
+    match params::parse_value(name, value)? {
+        size @ 1..=26 => Ok(size),
+        _ => Err(format!("{} should be between 1 and 26", name)),
+    }
+}
 
 pub struct TuningTrouble;
 
 impl TuningTrouble {
-    fn find_marker(input: String, marker_size: usize) -> usize {
+    /// Position right after the first `marker_size` distinct characters, if any.
+    fn find_marker(input: &str, marker_size: usize) -> Option<usize> {
         let mut queue = VecDeque::new();
         for (i, c) in input.chars().enumerate() {
+            cancel::checkpoint();
             if queue.len() < marker_size {
                 queue.push_back(c);
             }
             if queue.iter().collect::<HashSet<_>>().len() == marker_size {
-                return i + 1;
+                return Some(i + 1);
             } else if queue.len() == marker_size {
                 queue.pop_front();
             }
         }
 
-        panic!("WRONG INPUT")
+        None
     }
 }
 
@@ -27,20 +71,103 @@ impl Problem for TuningTrouble {
     type InputData = String;
     type OutputDataFirstPart = usize;
     type OutputDataSecondPart = usize;
+    type Params = TuningParams;
+
+    fn parse(reader: impl BufRead) -> Result<Self::InputData, ParseError> {
+        let lines = parse::read_lines(reader)?;
+        lines
+            .into_iter()
+            .next()
+            .ok_or_else(|| ParseError::input("input is empty"))
+    }
 
-    fn read_file(filename: impl AsRef<Path>) -> Self::InputData {
-        let file = std::fs::File::open(filename).unwrap();
-        let reader = BufReader::new(file);
-        reader.lines().map(Result::unwrap).next().unwrap()
+    fn first_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataFirstPart> {
+        TuningTrouble::find_marker(input, params.packet_marker)
     }
 
-    fn first_part(input: Self::InputData) -> Self::OutputDataFirstPart {
-        const MARKER_SIZE: usize = 4;
-        TuningTrouble::find_marker(input, MARKER_SIZE)
+    fn second_part(
+        input: &Self::InputData,
+        params: &Self::Params,
+    ) -> Option<Self::OutputDataSecondPart> {
+        TuningTrouble::find_marker(input, params.message_marker)
+    }
+}
+
+impl Generate for TuningTrouble {
+    /// `size` characters without any marker, followed by the first start-of-message marker.
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        let mut letters = ('a'..='z').collect::<Vec<_>>();
+        rng.shuffle(&mut letters);
+
+        // Three letters can not form even a start-of-packet marker
+        let mut signal = (0..size)
+            .map(|_| *rng.pick(&letters[..3]))
+            .collect::<String>();
+        rng.shuffle(&mut letters);
+        signal.extend(&letters[..14]);
+
+        signal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
+    #[test]
+    fn first_part_example() {
+        let input = TuningTrouble::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            TuningTrouble::first_part(&input, &Default::default()),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn second_part_example() {
+        let input = TuningTrouble::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            TuningTrouble::second_part(&input, &Default::default()),
+            Some(19)
+        );
+    }
+
+    #[test]
+    fn marker_sizes() {
+        let mut params = TuningParams::default();
+        assert_eq!(
+            params.set("packet_marker", "0"),
+            Err("packet_marker should be between 1 and 26".to_string())
+        );
+        assert!(params.set("message_marker", "27").is_err());
+
+        params.set("packet_marker", "26").unwrap();
+        let input = TuningTrouble::parse_str(EXAMPLE).unwrap();
+        assert_eq!(TuningTrouble::first_part(&input, &params), None);
+    }
+
+    fn reference(input: &str) -> (usize, Option<usize>) {
+        let signal = input.chars().collect::<Vec<_>>();
+        let marker = |size: usize| {
+            (size..=signal.len())
+                .find(|&end| {
+                    let window = &signal[end - size..end];
+                    (0..size).all(|i| (i + 1..size).all(|j| window[i] != window[j]))
+                })
+                .unwrap()
+        };
+
+        (marker(4), Some(marker(14)))
     }
 
-    fn second_part(input: Self::InputData) -> Option<Self::OutputDataSecondPart> {
-        const MARKER_SIZE: usize = 14;
-        Some(TuningTrouble::find_marker(input, MARKER_SIZE))
+    #[test]
+    fn matches_reference() {
+        crate::generate::assert_matches_reference::<TuningTrouble>(500, reference);
     }
 }
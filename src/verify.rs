@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::BufRead;
+use std::path::Path;
+
+use advent_of_code_2022::parse::{self, ParseError};
+use advent_of_code_2022::registry::{self, Day, Part};
+
+use crate::runner::Outcome;
+
+/// Expected answers, stored as one `<day> <part> <answer>` line per day/part.
+///
+/// Newlines and backslashes inside answers are escaped as `\n` and `\\`,
+/// so multi-line answers such as the CRT drawing fit into a single line.
+/// Empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    answers: BTreeMap<(u8, Part), String>,
+}
+
+impl Manifest {
+    pub fn parse(reader: impl BufRead) -> Result<Self, ParseError> {
+        let lines = parse::read_lines(reader)?;
+        let mut manifest = Manifest::default();
+
+        for line in parse::numbered(&lines) {
+            if line.text.trim().is_empty() || line.text.starts_with('#') {
+                continue;
+            }
+
+            let mut tokens = line.text.splitn(3, ' ');
+            let (Some(day), Some(part), Some(answer)) =
+                (tokens.next(), tokens.next(), tokens.next())
+            else {
+                return Err(line.error("expected '<day> <part> <answer>'"));
+            };
+            let day = registry::find(day)
+                .ok_or_else(|| line.error_at(day, format!("unknown day '{}'", day)))?;
+            let part = Part::from_number(line.parse(part)?)
+                .ok_or_else(|| line.error_at(part, "part should be 1 or 2"))?;
+            let answer = unescape(answer).map_err(|err| line.error_at(answer, err))?;
+
+            if manifest
+                .answers
+                .insert((day.number, part), answer)
+                .is_some()
+            {
+                return Err(line.error("duplicate answer"));
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn read_file(filename: impl AsRef<Path>) -> Result<Self, ParseError> {
+        let filename = filename.as_ref();
+        let file =
+            std::fs::File::open(filename).map_err(|err| ParseError::io(err).with_file(filename))?;
+
+        Self::parse(std::io::BufReader::new(file)).map_err(|err| err.with_file(filename))
+    }
+
+    pub fn get(&self, day: u8, part: Part) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: u8, part: Part, answer: &str) {
+        self.answers.insert((day, part), answer.to_string());
+    }
+
+    /// Records the answers of every solved day, keeping the expected answers of the others.
+    ///
+    /// Returns the days that were not solved, with the reason.
+    pub fn record<'a>(
+        &mut self,
+        results: &'a [(&'static Day, Outcome)],
+    ) -> Vec<(&'static Day, &'a str)> {
+        let mut skipped = vec![];
+
+        for (day, outcome) in results {
+            match outcome {
+                Outcome::Solved(profile) => {
+                    for part in Part::ALL {
+                        if let Some(answer) = profile.answers.get(part) {
+                            self.insert(day.number, part, answer);
+                        }
+                    }
+                }
+                Outcome::Failed(reason) | Outcome::TimedOut(reason) => {
+                    skipped.push((*day, reason.as_str()))
+                }
+            }
+        }
+
+        skipped
+    }
+
+    /// Registered days that have at least one expected answer.
+    pub fn days(&self) -> impl Iterator<Item = &'static Day> + '_ {
+        registry::DAYS.iter().filter(|day| {
+            Part::ALL
+                .iter()
+                .any(|&part| self.get(day.number, part).is_some())
+        })
+    }
+}
+
+impl std::fmt::Display for Manifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ((day, part), answer) in &self.answers {
+            writeln!(f, "{} {} {}", day, part.number(), escape(answer))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn escape(answer: &str) -> String {
+    answer.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(answer: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(answer.len());
+    let mut chars = answer.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('\\') => result.push('\\'),
+            Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+            None => return Err("dangling '\\' at the end of answer".to_string()),
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug)]
+pub enum Check {
+    Passed,
+    Mismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+    Failed(String),
+    TimedOut(String),
+}
+
+/// Compares solved days against the manifest, one check per expected answer.
+pub fn check(
+    manifest: &Manifest,
+    results: &[(&'static Day, Outcome)],
+) -> Vec<(&'static Day, Part, Check)> {
+    let mut checks = vec![];
+
+    for (day, outcome) in results {
+        for part in Part::ALL {
+            let Some(expected) = manifest.get(day.number, part) else {
+                continue;
+            };
+
+            let check = match outcome {
+                Outcome::Solved(profile) => match profile.answers.get(part) {
+                    Some(actual) if actual == expected => Check::Passed,
+                    actual => Check::Mismatch {
+                        expected: expected.to_string(),
+                        actual: actual.cloned(),
+                    },
+                },
+                Outcome::Failed(reason) => Check::Failed(reason.clone()),
+                Outcome::TimedOut(reason) => Check::TimedOut(reason.clone()),
+            };
+            checks.push((*day, part, check));
+        }
+    }
+
+    checks
+}
+
+pub fn format_checks(checks: &[(&'static Day, Part, Check)]) -> String {
+    let mut report = String::new();
+    let mut passed = 0;
+
+    for (day, part, check) in checks {
+        write!(
+            report,
+            "Day {:>2} part {} ({}): ",
+            day.number,
+            part.number(),
+            day.name
+        )
+        .unwrap();
+        match check {
+            Check::Passed => {
+                passed += 1;
+                writeln!(report, "ok").unwrap();
+            }
+            Check::Mismatch { expected, actual } => {
+                writeln!(report, "MISMATCH").unwrap();
+                report.push_str(&format_diff(expected, actual.as_deref()));
+            }
+            Check::Failed(reason) => writeln!(report, "FAILED: {}", reason).unwrap(),
+            Check::TimedOut(reason) => writeln!(report, "TIMEOUT: {}", reason).unwrap(),
+        }
+    }
+    write!(report, "{} of {} answers match", passed, checks.len()).unwrap();
+
+    report
+}
+
+/// Line-by-line diff: `-` lines are expected, `+` lines are actual.
+fn format_diff(expected: &str, actual: Option<&str>) -> String {
+    let mut diff = String::new();
+    let Some(actual) = actual else {
+        writeln!(diff, "    - {}", expected.replace('\n', "\n    - ")).unwrap();
+        writeln!(diff, "    + <no answer>").unwrap();
+        return diff;
+    };
+
+    let mut expected = expected.lines();
+    let mut actual = actual.lines();
+    loop {
+        match (expected.next(), actual.next()) {
+            (None, None) => break,
+            (Some(l), Some(r)) if l == r => writeln!(diff, "      {}", l).unwrap(),
+            (l, r) => {
+                if let Some(l) = l {
+                    writeln!(diff, "    - {}", l).unwrap();
+                }
+                if let Some(r) = r {
+                    writeln!(diff, "    + {}", r).unwrap();
+                }
+            }
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{Profile, Timings};
+    use advent_of_code_2022::registry::Answers;
+
+    const CRT: &str = "##..\n.#\\.";
+
+    fn solved(first: &str, second: Option<&str>) -> Outcome {
+        Outcome::Solved(Profile {
+            answers: Answers {
+                first: Some(first.to_string()),
+                second: second.map(str::to_string),
+            },
+            timings: Timings::default(),
+            memory: None,
+        })
+    }
+
+    #[test]
+    fn manifest_round_trip() {
+        let mut manifest = Manifest::default();
+        manifest.insert(1, Part::First, "24000");
+        manifest.insert(10, Part::Second, CRT);
+
+        let text = manifest.to_string();
+        assert_eq!(text, "1 1 24000\n10 2 ##..\\n.#\\\\.\n");
+        assert_eq!(Manifest::parse(text.as_bytes()).unwrap(), manifest);
+        assert_eq!(manifest.get(10, Part::Second), Some(CRT));
+
+        let parsed = Manifest::parse("# comment\n\n5 1 CMZ\n".as_bytes()).unwrap();
+        assert_eq!(parsed.get(5, Part::First), Some("CMZ"));
+        assert_eq!(parsed.days().map(|day| day.number).collect::<Vec<_>>(), [5]);
+    }
+
+    #[test]
+    fn reject_bad_manifests() {
+        let error = |text: &str| Manifest::parse(text.as_bytes()).unwrap_err();
+
+        let err = error("1 1 24000\n1 1 24001");
+        assert_eq!((err.line, err.message.as_str()), (2, "duplicate answer"));
+        let err = error("26 1 0");
+        assert_eq!(err.message, "unknown day '26'");
+        assert_eq!(error("1 3 0").message, "part should be 1 or 2");
+        assert_eq!(error("1 1").message, "expected '<day> <part> <answer>'");
+        assert_eq!(error("1 1 a\\tb").message, "unknown escape '\\t'");
+        assert_eq!(
+            error("1 1 a\\").message,
+            "dangling '\\' at the end of answer"
+        );
+    }
+
+    #[test]
+    fn record_keeps_unsolved_days() {
+        let manifest = Manifest::parse("1 1 1\n1 2 2\n2 1 old\n".as_bytes()).unwrap();
+        let results = [
+            (registry::find("1").unwrap(), solved("10", None)),
+            (
+                registry::find("2").unwrap(),
+                Outcome::Failed("panicked".to_string()),
+            ),
+        ];
+
+        let mut updated = manifest.clone();
+        let skipped = updated.record(&results);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!((skipped[0].0.number, skipped[0].1), (2, "panicked"));
+        assert_eq!(updated.get(1, Part::First), Some("10"));
+        assert_eq!(updated.get(1, Part::Second), Some("2"));
+        assert_eq!(updated.get(2, Part::First), Some("old"));
+    }
+
+    #[test]
+    fn check_answers() {
+        let manifest =
+            Manifest::parse("1 1 24000\n1 2 45000\n2 1 15\n3 2 70\n".as_bytes()).unwrap();
+        let results = [
+            (registry::find("1").unwrap(), solved("24000", Some("45001"))),
+            (
+                registry::find("2").unwrap(),
+                Outcome::TimedOut("first part".to_string()),
+            ),
+            (registry::find("3").unwrap(), solved("157", None)),
+        ];
+
+        let checks = check(&manifest, &results);
+        let kinds = checks
+            .iter()
+            .map(|(day, part, _)| (day.number, *part))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (1, Part::First),
+                (1, Part::Second),
+                (2, Part::First),
+                (3, Part::Second)
+            ]
+        );
+        assert!(matches!(checks[0].2, Check::Passed));
+        assert!(matches!(
+            &checks[1].2,
+            Check::Mismatch { expected, actual } if expected == "45000" && actual.as_deref() == Some("45001")
+        ));
+        assert!(matches!(&checks[2].2, Check::TimedOut(reason) if reason == "first part"));
+        assert!(matches!(&checks[3].2, Check::Mismatch { actual: None, .. }));
+
+        let report = format_checks(&checks);
+        assert!(report
+            .contains("Day  1 part 2 (calorie_counting): MISMATCH\n    - 45000\n    + 45001\n"));
+        assert!(report.contains("Day  2 part 1 (rock_paper_scissors): TIMEOUT: first part\n"));
+        assert!(report.ends_with("1 of 4 answers match"));
+    }
+
+    #[test]
+    fn diff_lines() {
+        assert_eq!(
+            format_diff("##\n..\n#.", Some("##\n.#")),
+            "      ##\n    - ..\n    + .#\n    - #.\n"
+        );
+        assert_eq!(
+            format_diff("a\nb", None),
+            "    - a\n    - b\n    + <no answer>\n"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use advent_of_code_2022::dyn_problem::DynProblem;
+use advent_of_code_2022::params::Overrides;
+use advent_of_code_2022::parse::ParseError;
+use advent_of_code_2022::registry::Day;
+
+/// Where the frames of a visualization go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// One file per frame, `00001.txt` onwards, in the directory.
+    Files(PathBuf),
+    /// Redraws the terminal with every frame, pausing between them.
+    Play(Duration),
+}
+
+/// Parses `filename` and feeds the frames of its visualization to `frame`.
+///
+/// `Ok(false)` means the day has no visualization.
+pub fn frames(
+    problem: &dyn DynProblem,
+    filename: &Path,
+    overrides: &Overrides,
+    frame: &mut dyn FnMut(String),
+) -> Result<bool, ParseError> {
+    let params = problem.params(overrides).map_err(ParseError::input)?;
+    let data = problem.read_file(filename)?;
+
+    Ok(problem.visualize(&*data, &*params, frame))
+}
+
+/// Sends the frames of `day` on `input` to `output`, returning how many there were.
+pub fn export(
+    day: &Day,
+    input: &Path,
+    overrides: &Overrides,
+    output: &Output,
+) -> Result<usize, String> {
+    let mut count = 0;
+    let mut failure = None;
+
+    let visualized = match output {
+        Output::Files(dir) => {
+            fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+            frames(day.problem, input, overrides, &mut |frame| {
+                if failure.is_some() {
+                    return;
+                }
+                count += 1;
+                let path = dir.join(format!("{:05}.txt", count));
+                if let Err(err) = fs::write(&path, frame + "\n") {
+                    failure = Some(format!("{}: {}", path.display(), err));
+                }
+            })
+        }
+        Output::Play(delay) => frames(day.problem, input, overrides, &mut |frame| {
+            if failure.is_some() {
+                return;
+            }
+            count += 1;
+            // Clear the screen and move the cursor home before drawing
+            let mut stdout = io::stdout().lock();
+            match writeln!(stdout, "\x1b[2J\x1b[H{}", frame).and_then(|_| stdout.flush()) {
+                Ok(()) => thread::sleep(*delay),
+                Err(err) => failure = Some(format!("stdout: {}", err)),
+            }
+        }),
+    }
+    .map_err(|err| err.to_string())?;
+
+    if !visualized {
+        return Err(format!("Day {} has no visualization", day.number));
+    }
+
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2022::registry;
+
+    #[test]
+    fn export_numbered_files() {
+        let dir = std::env::temp_dir().join(format!("aoc-2022-visualize-{}", std::process::id()));
+        let input = dir.join("input.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&input, "R 2\nU 1").unwrap();
+
+        let frames = dir.join("frames");
+        let day = registry::find("rope_bridge").unwrap();
+        let count = export(
+            day,
+            &input,
+            &Overrides::default(),
+            &Output::Files(frames.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(count, 3);
+        assert!(fs::read_to_string(frames.join("00003.txt"))
+            .unwrap()
+            .contains('H'));
+
+        fs::write(&input, "1000\n\n2000").unwrap();
+        let err = export(
+            registry::find("calorie_counting").unwrap(),
+            &input,
+            &Overrides::default(),
+            &Output::Files(frames),
+        );
+        assert_eq!(err, Err("Day 1 has no visualization".to_string()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use advent_of_code_2022::params::Overrides;
+use advent_of_code_2022::registry::{Answers, Day, Part};
+
+use crate::runner::{self, Outcome, Profile};
+
+/// Notices edits of a file by polling its modification time and size.
+#[derive(Debug)]
+pub struct Watcher {
+    path: PathBuf,
+    /// `None` until the first poll; a missing file is seen as `Some(None)`.
+    seen: Option<Option<(SystemTime, u64)>>,
+}
+
+impl Watcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            seen: None,
+        }
+    }
+
+    /// Whether the file changed since the last call; always `true` on the first one.
+    pub fn changed(&mut self) -> bool {
+        let current = fs::metadata(&self.path)
+            .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+            .ok();
+        if self.seen == Some(current) {
+            return false;
+        }
+
+        self.seen = Some(current);
+        true
+    }
+}
+
+/// Re-solves `day` every time `input` changes, checking every `interval`, and never returns.
+///
+/// Each run prints the answers and timings next to the last successful answers.
+pub fn watch(day: &Day, input: &Path, overrides: &Overrides, interval: Duration) -> ! {
+    let mut watcher = Watcher::new(input);
+    let mut previous = None;
+
+    loop {
+        if watcher.changed() {
+            let outcome = runner::with_silent_panics(|| {
+                runner::run_day(day, input, overrides, None, Some(runner::DEFAULT_TIMEOUT))
+            });
+            println!("--- {} ---", input.display());
+            print!("{}", format_run(day, &outcome, previous.as_ref()));
+
+            if let Outcome::Solved(profile) = outcome {
+                previous = Some(profile.answers);
+            }
+        }
+
+        thread::sleep(interval);
+    }
+}
+
+/// Answers and timings of a run, each answer compared with the `previous` one.
+pub fn format_run(day: &Day, outcome: &Outcome, previous: Option<&Answers>) -> String {
+    let mut report = String::new();
+
+    let Outcome::Solved(Profile {
+        answers, timings, ..
+    }) = outcome
+    else {
+        match outcome {
+            Outcome::Failed(reason) => {
+                writeln!(report, "Day {} failed: {}", day.number, reason).unwrap()
+            }
+            Outcome::TimedOut(reason) => {
+                writeln!(report, "Day {} timed out: {}", day.number, reason).unwrap()
+            }
+            Outcome::Solved(_) => unreachable!(),
+        }
+        return report;
+    };
+
+    writeln!(
+        report,
+        "Day {}: {} (parse {})",
+        day.number,
+        day.name,
+        runner::format_duration(timings.parse)
+    )
+    .unwrap();
+    for part in Part::ALL {
+        let time = match part {
+            Part::First => timings.first,
+            Part::Second => timings.second,
+        };
+        let answer = answers.get(part);
+        let comparison = match previous.map(|previous| previous.get(part)) {
+            None => String::new(),
+            Some(previous) if previous == answer => ", unchanged".to_string(),
+            Some(Some(previous)) if !previous.contains('\n') => format!(", was {}", previous),
+            Some(_) => ", changed".to_string(),
+        };
+        let time = runner::format_duration(time);
+
+        match answer {
+            Some(answer) if answer.contains('\n') => writeln!(
+                report,
+                "Part {} ({}{}):\n{}",
+                part.number(),
+                time,
+                comparison,
+                answer
+            ),
+            answer => writeln!(
+                report,
+                "Part {}: {} ({}{})",
+                part.number(),
+                answer.map_or("-", String::as_str),
+                time,
+                comparison
+            ),
+        }
+        .unwrap();
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Timings;
+    use advent_of_code_2022::registry;
+
+    #[test]
+    fn watcher_notices_changes() {
+        let path = std::env::temp_dir().join(format!("aoc-2022-watch-{}.txt", std::process::id()));
+        let mut watcher = Watcher::new(&path);
+
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::write(&path, "1").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::write(&path, "12").unwrap();
+        assert!(watcher.changed());
+
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+    }
+
+    #[test]
+    fn compare_with_previous_answers() {
+        let day = registry::find("calorie_counting").unwrap();
+        let answers = |first: &str| Answers {
+            first: Some(first.to_string()),
+            second: Some("45000".to_string()),
+        };
+        let outcome = Outcome::Solved(Profile {
+            answers: answers("24000"),
+            timings: Timings::default(),
+            memory: None,
+        });
+
+        let report = format_run(day, &outcome, None);
+        assert_eq!(
+            report.lines().skip(1).collect::<Vec<_>>(),
+            ["Part 1: 24000 (0.00ns)", "Part 2: 45000 (0.00ns)"]
+        );
+
+        let report = format_run(day, &outcome, Some(&answers("11000")));
+        assert_eq!(
+            report.lines().skip(1).collect::<Vec<_>>(),
+            [
+                "Part 1: 24000 (0.00ns, was 11000)",
+                "Part 2: 45000 (0.00ns, unchanged)"
+            ]
+        );
+
+        let failed = Outcome::Failed("line 1: oops".to_string());
+        assert_eq!(
+            format_run(day, &failed, None),
+            "Day 1 failed: line 1: oops\n"
+        );
+    }
+}